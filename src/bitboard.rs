@@ -0,0 +1,250 @@
+#[cfg(feature = "std")]
+use std::sync::OnceLock;
+
+#[cfg(feature = "std")]
+use crate::game::{Board, Core};
+use crate::types::{Coordinate, Direction, Tile, BLANK};
+
+/// The largest stored value of a packed tile, such tiles never merge
+/// since the merged value would not fit in 4 bits.
+const MAX_PACKED: Tile = 15;
+
+/// A 4x4 board packed into a u64 for the fast rollouts of solvers,
+/// 4 bits per tile holding the stored value, the tile (r, c) takes
+/// the bits from (r * 4 + c) * 4. It needs neither `std` nor allocation,
+/// so it also backs the game on embedded displays.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BitBoard(pub u64);
+
+impl BitBoard {
+    /// Packs the board, returns None if it is not 4x4, has walls or some
+    /// tile is 15 or larger, since two 15s merge on the unpacked board but
+    /// not in 4 bits. The scores and the moves are not packed.
+    #[cfg(feature = "std")]
+    pub fn from_board(board: &Board) -> Option<BitBoard> {
+        if board.rows() != 4 || board.cols() != 4 || board.walls().next().is_some() {
+            return None;
+        }
+        let mut bits = BitBoard::default();
+        for (pos, tile) in board.iter() {
+            if tile >= MAX_PACKED {
+                return None;
+            }
+            bits.set(&pos, tile);
+        }
+        Some(bits)
+    }
+
+    /// Unpacks the tiles into a 4x4 board without scores.
+    #[cfg(feature = "std")]
+    pub fn to_board(self) -> Board {
+        let tiles = (0..16)
+            .map(|i| ((self.0 >> (i * 4)) & 0xf) as Tile)
+            .collect();
+        Board::new(4, Some(tiles), 0)
+    }
+
+    /// Returns the stored value of a tile, or None if it is out of the board.
+    pub fn get(self, pos: &Coordinate) -> Option<Tile> {
+        if pos.0 >= 4 || pos.1 >= 4 {
+            return None;
+        }
+        Some(((self.0 >> ((pos.0 * 4 + pos.1) * 4)) & 0xf) as Tile)
+    }
+
+    /// Sets the stored value of a tile, panics if the position is out of
+    /// the board or the value doesn't fit in 4 bits.
+    pub fn set(&mut self, pos: &Coordinate, value: Tile) {
+        assert!(pos.0 < 4 && pos.1 < 4, "The position is out of the board.");
+        assert!(
+            (BLANK..=MAX_PACKED).contains(&value),
+            "The tile value must be in 0..=15."
+        );
+        let shift = (pos.0 * 4 + pos.1) * 4;
+        self.0 = (self.0 & !(0xf << shift)) | ((value as u64) << shift);
+    }
+
+    /// Returns the number of blank tiles.
+    pub fn count_empty(self) -> usize {
+        (0..16).filter(|i| (self.0 >> (i * 4)) & 0xf == 0).count()
+    }
+
+    /// Shifts by the direction by the classic rule and returns the result
+    /// with the gained scores, the board is unchanged for an invalid move.
+    pub fn shift(self, direction: &Direction) -> (BitBoard, u32) {
+        let mut next = self;
+        let mut gained = 0;
+        for i in 0..4 {
+            let (line, points) = match direction {
+                Direction::Left => shift_row(self.row(i)),
+                Direction::Right => shift_reversed(self.row(i)),
+                Direction::Up => shift_row(self.column(i)),
+                Direction::Down => shift_reversed(self.column(i)),
+            };
+            match direction {
+                Direction::Left | Direction::Right => next.set_row(i, line),
+                Direction::Up | Direction::Down => next.set_column(i, line),
+            }
+            gained += points;
+        }
+        (next, gained)
+    }
+
+    /// Checks whether no direction changes the board.
+    pub fn is_game_over(self) -> bool {
+        Direction::all().iter().all(|d| self.shift(d).0 == self)
+    }
+
+    fn row(self, r: usize) -> u16 {
+        (self.0 >> (r * 16)) as u16
+    }
+
+    fn set_row(&mut self, r: usize, row: u16) {
+        self.0 = (self.0 & !(0xffff << (r * 16))) | ((row as u64) << (r * 16));
+    }
+
+    /// Returns the column as a row, the top tile in the lowest bits.
+    fn column(self, c: usize) -> u16 {
+        (0..4).fold(0, |acc, r| {
+            acc | ((((self.0 >> ((r * 4 + c) * 4)) & 0xf) as u16) << (r * 4))
+        })
+    }
+
+    fn set_column(&mut self, c: usize, column: u16) {
+        for r in 0..4 {
+            let shift = (r * 4 + c) * 4;
+            let tile = ((column >> (r * 4)) & 0xf) as u64;
+            self.0 = (self.0 & !(0xf << shift)) | (tile << shift);
+        }
+    }
+}
+
+/// Looks up the row shifted towards its lowest bits with the gained scores,
+/// the table of all 65536 rows is built on the first use.
+#[cfg(feature = "std")]
+fn shift_row(row: u16) -> (u16, u32) {
+    static TABLE: OnceLock<Vec<(u16, u32)>> = OnceLock::new();
+    TABLE.get_or_init(|| (0..=u16::MAX).map(compute_row).collect())[row as usize]
+}
+
+/// Computes the shifted row on every call without the `std` feature,
+/// which saves the memory of the table on small devices.
+#[cfg(not(feature = "std"))]
+fn shift_row(row: u16) -> (u16, u32) {
+    compute_row(row)
+}
+
+/// Shifts a row towards its lowest bits by the classic rule, every tile
+/// merges at most once.
+fn compute_row(row: u16) -> (u16, u32) {
+    let mut tiles = [BLANK; 4];
+    let mut len = 0;
+    for i in 0..4 {
+        let tile = ((row >> (i * 4)) & 0xf) as Tile;
+        if tile != BLANK {
+            tiles[len] = tile;
+            len += 1;
+        }
+    }
+
+    let mut packed = 0;
+    let mut gained = 0;
+    let (mut i, mut at) = (0, 0);
+    while i < len {
+        let mut tile = tiles[i];
+        if i + 1 < len && tiles[i] == tiles[i + 1] && tile < MAX_PACKED {
+            gained += 1 << (tile + 1);
+            tile += 1;
+            i += 2;
+        } else {
+            i += 1;
+        }
+        packed |= (tile as u16) << (at * 4);
+        at += 1;
+    }
+    (packed, gained)
+}
+
+/// Reverses the order of the 4 tiles of a row.
+fn reverse_row(row: u16) -> u16 {
+    (row >> 12) | ((row >> 4) & 0x00f0) | ((row << 4) & 0x0f00) | (row << 12)
+}
+
+fn shift_reversed(row: u16) -> (u16, u32) {
+    let (shifted, gained) = shift_row(reverse_row(row));
+    (reverse_row(shifted), gained)
+}
+
+#[cfg(feature = "std")]
+impl Core {
+    /// Shifts the packed board like `BitBoard::shift`, the same as `shift`
+    /// on the unpacked board by the classic rule, whatever the rule of this
+    /// core is.
+    pub fn shift_fast(&self, board: BitBoard, direction: &Direction) -> (BitBoard, u32) {
+        board.shift(direction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![cfg_attr(rustfmt, rustfmt_skip)]
+
+    use super::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    #[test]
+    fn test_shift_fast() {
+        let core = Core::new();
+        let mut rng = StdRng::seed_from_u64(2048);
+        for _ in 0..10000 {
+            let tiles = (0..16)
+                .map(|_| if rng.gen_bool(0.3) { BLANK } else { rng.gen_range(1..=15) })
+                .collect();
+            let board = Board::new(4, Some(tiles), 0);
+            let bits = match BitBoard::from_board(&board) {
+                Some(bits) => bits,
+                None => {
+                    assert!(board.max_tile() == MAX_PACKED);
+                    continue;
+                }
+            };
+            assert_eq!(bits.to_board(), board);
+            assert_eq!(bits.count_empty(), board.count_empty());
+            assert_eq!(bits.is_game_over(), core.is_game_over(&board));
+
+            for direction in Direction::all().iter() {
+                let mut expected = board.clone();
+                let gained = core.shift(&mut expected, direction).gained;
+                let (next, points) = core.shift_fast(bits, direction);
+                assert_eq!(next.to_board().as_exponent_vec(), expected.as_exponent_vec(), "{:?} {}", board.as_exponent_vec(), direction);
+                assert_eq!(points, gained);
+            }
+        }
+
+        assert_eq!(BitBoard::from_board(&Board::new(3, None, 0)), None);
+    }
+
+    #[test]
+    fn test_from_board_rejects_unmergeable() {
+        // Two 15s merge into 16 on the board, which doesn't fit in 4 bits.
+        let board = Board::new(4, Some(vec![15, 15, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]), 0);
+        assert_eq!(BitBoard::from_board(&board), None);
+        let mut shifted = board.clone();
+        assert_eq!(Core::new().shift(&mut shifted, &Direction::Left).merges, vec![(0, 0)]);
+        assert_eq!(shifted.get(&(0, 0)), Some(&16));
+
+        let board = Board::new(4, Some(vec![14, 14, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]), 0);
+        let (next, points) = BitBoard::from_board(&board).unwrap().shift(&Direction::Left);
+        assert_eq!(next.get(&(0, 0)), Some(15));
+        assert_eq!(points, 1 << 15);
+    }
+
+    #[test]
+    fn test_get_and_set() {
+        let mut bits = BitBoard::default();
+        bits.set(&(1, 2), 15);
+        assert_eq!(bits.get(&(1, 2)), Some(15));
+        assert_eq!(bits.get(&(4, 0)), None);
+        assert_eq!(bits.count_empty(), 15);
+    }
+}
@@ -0,0 +1,199 @@
+use std::ops::RangeInclusive;
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::{Board, Core, Tile, TurnResult, TARGET};
+use crate::history::{History, DEFAULT_DEPTH};
+use crate::types::Direction;
+
+/// The sizes of the square boards which `GameConfig::with_size` accepts.
+pub const SIZES: RangeInclusive<usize> = 2..=16;
+
+/// The tunables of a game in one place, the default is the classic 2048.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GameConfig {
+    pub rows: usize,
+    pub cols: usize,
+    /// The number of tiles spawned at the start of a game.
+    pub start_tiles: u32,
+    /// The stored values with their weights which all spawns of the config
+    /// are drawn from by `Board::generate_weighted`.
+    pub spawn_weights: Vec<(Tile, f64)>,
+    /// The exponent a tile must reach to win.
+    pub target: Tile,
+    /// The number of moves that can be undone.
+    pub undo_depth: usize,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfig {
+            rows: 4,
+            cols: 4,
+            start_tiles: 2,
+            spawn_weights: vec![(1, 0.9), (2, 0.1)],
+            target: TARGET,
+            undo_depth: DEFAULT_DEPTH,
+        }
+    }
+}
+
+impl GameConfig {
+    /// Returns the default config on a square board of the size, or None if
+    /// the size is out of `SIZES`.
+    pub fn with_size(size: usize) -> Option<Self> {
+        if !SIZES.contains(&size) {
+            return None;
+        }
+        Some(GameConfig {
+            rows: size,
+            cols: size,
+            ..GameConfig::default()
+        })
+    }
+
+    /// Returns the number of tiles to spawn per turn, which grows with the
+    /// board so that a large board doesn't drag: 1 up to 5x5, 2 for 6x6 and
+    /// 7x7, 3 for 8x8 and so on.
+    pub fn spawns_for_size(&self) -> u32 {
+        let size = self.rows.max(self.cols) as u32;
+        (size.saturating_sub(2) / 2).max(1)
+    }
+}
+
+impl Board {
+    /// Creates a blank board of the configured size.
+    pub fn from_config(config: &GameConfig) -> Self {
+        Board::new_rect(config.rows, config.cols, None, 0)
+    }
+
+    /// Creates a board of the configured size with the start tiles spawned
+    /// by the configured weights.
+    pub fn new_game(config: &GameConfig) -> Self {
+        let mut board = Board::from_config(config);
+        board.generate_weighted(config.start_tiles, &config.spawn_weights);
+        board
+    }
+}
+
+impl Core {
+    /// Creates a core with the classic merge rule and the configured target.
+    pub fn from_config(config: &GameConfig) -> Self {
+        Core::new().with_target(config.target)
+    }
+
+    /// Plays a turn like `Core::play_turn`, spawning
+    /// `GameConfig::spawns_for_size` tiles by the configured weights and
+    /// checking the state for the configured target.
+    pub fn play_turn_with_config(
+        &self,
+        board: &mut Board,
+        direction: &Direction,
+        config: &GameConfig,
+    ) -> Option<TurnResult> {
+        self.play_turn_by(board, direction, config.target, |board| {
+            board.generate_weighted(config.spawns_for_size(), &config.spawn_weights)
+        })
+    }
+}
+
+impl History {
+    /// Creates a history of the configured undo depth.
+    pub fn from_config(config: &GameConfig) -> Self {
+        History::new(config.undo_depth)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_config() {
+        let config = GameConfig {
+            rows: 3,
+            cols: 5,
+            ..GameConfig::default()
+        };
+        let board = Board::from_config(&config);
+        assert_eq!((board.rows(), board.cols()), (3, 5));
+        assert_eq!(board.count_empty(), 15);
+
+        assert_eq!(config.spawns_for_size(), 1);
+        assert_eq!(GameConfig::default().spawns_for_size(), 1);
+        assert_eq!(
+            GameConfig {
+                rows: 6,
+                cols: 6,
+                ..GameConfig::default()
+            }
+            .spawns_for_size(),
+            2
+        );
+        assert_eq!(
+            GameConfig {
+                rows: 8,
+                cols: 8,
+                ..GameConfig::default()
+            }
+            .spawns_for_size(),
+            3
+        );
+
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(serde_json::from_str::<GameConfig>(&json).unwrap(), config);
+    }
+
+    #[test]
+    fn test_spawn_weights() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let config = GameConfig {
+            start_tiles: 3,
+            spawn_weights: vec![(1, 0.0), (3, 1.0)],
+            target: 4,
+            ..GameConfig::default()
+        };
+        let mut board = Board::new_game(&config);
+        assert_eq!(
+            board.iter_nonblank().map(|(_, t)| t).collect::<Vec<_>>(),
+            vec![3, 3, 3]
+        );
+
+        let core = Core::from_config(&config);
+        assert_eq!(core.target(), 4);
+        let direction = core.available_moves(&board)[0];
+        let turn = core
+            .play_turn_with_config(&mut board, &direction, &config)
+            .unwrap();
+        assert_eq!(turn.spawns.len(), 1);
+        assert_eq!(turn.spawns[0].1, 3);
+
+        // The default config spawns 2s nine times out of ten.
+        let config = GameConfig::with_size(16).unwrap();
+        let mut board = Board::from_config(&config);
+        let mut rng = StdRng::seed_from_u64(2048);
+        board.generate_weighted_with(&mut rng, 256, &config.spawn_weights);
+        assert!(board.is_full());
+        let twos = board.iter_nonblank().filter(|&(_, t)| t == 1).count();
+        assert_eq!(twos, 229);
+    }
+
+    #[test]
+    fn test_with_size() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        assert_eq!(GameConfig::with_size(1), None);
+        assert_eq!(GameConfig::with_size(17), None);
+        assert_eq!(GameConfig::with_size(4), Some(GameConfig::default()));
+
+        let core = crate::game::Core::new();
+        let mut rng = StdRng::seed_from_u64(2048);
+        for size in [5, 6].iter() {
+            let config = GameConfig::with_size(*size).unwrap();
+            let board = Board::new_game(&config);
+            let (score, _) = core.simulate_random_game(&board, &mut rng);
+            assert!(score > 0);
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::config::GameConfig;
+use crate::game::{Board, Core};
+use crate::types::Direction;
+
+/// A game behind a binding-free facade for front-ends like a browser: the
+/// arguments and the results are plain numbers and vectors, so that a
+/// front-end crate can export the methods by `wasm-bindgen` as they are.
+/// The spawns are drawn from a generator seeded by the caller instead of
+/// the thread generator, and nothing here touches the files or the logger.
+pub struct Game {
+    config: GameConfig,
+    board: Board,
+    core: Core,
+    rng: StdRng,
+}
+
+impl Game {
+    /// Starts a classic game on a square board with the start tiles spawned
+    /// by the generator of the seed, the same seed plays the same spawns.
+    pub fn new(size: usize, seed: u64) -> Self {
+        let config = GameConfig {
+            rows: size,
+            cols: size,
+            ..GameConfig::default()
+        };
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut board = Board::from_config(&config);
+        board.generate_weighted_with(&mut rng, config.start_tiles, &config.spawn_weights);
+        Game {
+            core: Core::from_config(&config),
+            config,
+            board,
+            rng,
+        }
+    }
+
+    /// Shifts by the direction numbered 0 up, 1 down, 2 left and 3 right,
+    /// returns whether any tile moved. An unknown number moves nothing.
+    pub fn shift(&mut self, direction: u8) -> bool {
+        match Direction::all().get(direction as usize) {
+            Some(direction) => self.board.apply_direction(&self.core, direction),
+            None => false,
+        }
+    }
+
+    /// Spawns a 2 or a 4 on a blank tile, if any.
+    pub fn spawn(&mut self) {
+        let weights = &self.config.spawn_weights;
+        self.board.generate_weighted_with(&mut self.rng, 1, weights);
+    }
+
+    pub fn score(&self) -> u32 {
+        self.board.score()
+    }
+
+    /// Checks whether no move is possible.
+    pub fn is_over(&self) -> bool {
+        self.core.is_game_over(&self.board)
+    }
+
+    /// Returns the stored values in row-major order, 0 for a blank tile.
+    pub fn cells(&self) -> Vec<i32> {
+        self.board.as_exponent_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_game() {
+        let mut game = Game::new(2, 2048);
+        assert_eq!(game.cells().iter().filter(|&&t| t != 0).count(), 2);
+        assert_eq!(game.cells(), Game::new(2, 2048).cells());
+        assert!(!game.shift(4));
+
+        game.board = Board::new(2, Some(vec![1, 1, 0, 0]), 0);
+        assert!(!game.shift(0));
+        assert!(game.shift(2));
+        assert_eq!(game.cells(), vec![2, 0, 0, 0]);
+        assert_eq!(game.score(), 4);
+        game.spawn();
+        assert_eq!(game.cells().iter().filter(|&&t| t != 0).count(), 2);
+        assert!(!game.is_over());
+    }
+}
@@ -1,286 +1,3109 @@
-use std::{collections::VecDeque, error, fmt, fs::write, ops::Range};
+use std::{
+    collections::{BTreeSet, HashMap},
+    convert::TryFrom,
+    error, fmt,
+    fs::{read, read_to_string, write},
+    io,
+    ops::Range,
+    str::FromStr,
+};
 
-use rand::{thread_rng, Rng};
+use rand::{
+    distributions::{Distribution, WeightedIndex},
+    thread_rng, Rng,
+};
 use serde::{Deserialize, Serialize};
 
-const BLANK: i32 = 0;
+use crate::types::BLANK;
+pub use crate::types::{Coordinate, Direction, Tile, Trace};
 
-pub enum Direction {
-    Up,
-    Down,
-    Left,
-    Right,
-}
+mod ai;
+mod eval;
+mod rule;
+
+pub use eval::{snake_weights, Corner, Heuristics};
+pub use rule::{ClassicRule, MergeRule};
+
+/// The exponent of the 2048 tile.
+pub const TARGET: Tile = 11;
+
+/// The largest stored value whose real value fits in u64.
+const MAX_EXPONENT: Tile = 63;
+/// The ANSI 256-color codes of the stored values 1 to 11 (2 to 2048),
+/// the larger tiles use the last one.
+const ANSI_PALETTE: [u8; 11] = [250, 223, 215, 209, 203, 196, 227, 226, 220, 214, 208];
+const BIN_MAGIC: &[u8] = b"2048";
+const BIN_HEADER_LEN: usize = 16;
 
-type Tile = i32;
-type Coordinate = (usize, usize);
-type Trace = (Coordinate, Coordinate);
+/// The offset basis and the prime of the 64-bit FNV-1a hash.
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
 
 /// The type of game board data contains:
-///     1.the size of the board
+///     1.the rows and columns of the board
 ///     2.the two-dimensional array of the tiles value,
-///       the real value = 0 << (the stored value)
-///     3.the scores of current situation.
-#[derive(Debug, Serialize, Deserialize)]
+///       the real value = 1 << (the stored value)
+///     3.the scores of current situation
+///     4.the number of successful moves
+///     5.the highest stored value ever produced by a merge
+///     6.the directions of the successful moves, only if recording is enabled
+///     7.the wall cells which tiles can neither pass nor merge with.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "BoardData")]
 pub struct Board {
-    size: usize,
+    rows: usize,
+    cols: usize,
     tiles: Vec<Vec<Tile>>,
     score: u32,
+    #[serde(default)]
+    moves: u32,
+    #[serde(default)]
+    highest_merged: Tile,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    recording: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    history: Vec<Direction>,
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    walls: BTreeSet<Coordinate>,
 }
 
-impl fmt::Display for Board {
+/// The json representation of a board as read, which also accepts the
+/// `size` of the square boards saved before the rows and columns.
+#[derive(Deserialize)]
+struct BoardData {
+    #[serde(default)]
+    size: Option<usize>,
+    #[serde(default)]
+    rows: Option<usize>,
+    #[serde(default)]
+    cols: Option<usize>,
+    tiles: Vec<Vec<Tile>>,
+    score: u32,
+    #[serde(default)]
+    moves: u32,
+    #[serde(default)]
+    highest_merged: Tile,
+    #[serde(default)]
+    recording: bool,
+    #[serde(default)]
+    history: Vec<Direction>,
+    #[serde(default)]
+    walls: BTreeSet<Coordinate>,
+}
+
+impl TryFrom<BoardData> for Board {
+    type Error = String;
+
+    fn try_from(data: BoardData) -> Result<Self, Self::Error> {
+        let (rows, cols) = match (data.rows, data.cols, data.size) {
+            (Some(rows), Some(cols), _) => (rows, cols),
+            (None, None, Some(size)) => (size, size),
+            _ => return Err(String::from("missing the rows and columns of the board")),
+        };
+        Ok(Board {
+            rows,
+            cols,
+            tiles: data.tiles,
+            score: data.score,
+            moves: data.moves,
+            highest_merged: data.highest_merged,
+            recording: data.recording,
+            history: data.history,
+            walls: data.walls,
+        })
+    }
+}
+
+/// The json representation of a board storing the real values.
+#[derive(Serialize, Deserialize)]
+struct RealBoard {
+    rows: usize,
+    cols: usize,
+    tiles: Vec<Vec<u64>>,
+    score: u32,
+    #[serde(default)]
+    moves: u32,
+    #[serde(default)]
+    highest_merged: Tile,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    recording: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    history: Vec<Direction>,
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    walls: BTreeSet<Coordinate>,
+}
+
+/// Converts a stored value to the real value, or None beyond 2^63, which
+/// only `validate` rejects.
+fn real_value(t: Tile) -> Option<u64> {
+    match t {
+        BLANK => Some(0),
+        _ => 1u64.checked_shl(t as u32),
+    }
+}
+
+/// Writes the real value of a stored value for the renderings, as a power
+/// like "2^64" beyond 2^63.
+fn real_label(t: Tile) -> String {
+    real_value(t).map_or_else(|| format!("2^{}", t), |v| v.to_string())
+}
+
+/// The error returned when an operation would break the board.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BoardError {
+    OutOfBounds(Coordinate),
+    NegativeTile(Tile),
+    SizeMismatch {
+        expected: (usize, usize),
+        found: (usize, usize),
+    },
+    RowCount {
+        expected: usize,
+        found: usize,
+    },
+    ColumnCount {
+        row: usize,
+        expected: usize,
+        found: usize,
+    },
+    ImplausibleTile(Coordinate, Tile),
+    Occupied(Coordinate),
+}
+
+impl fmt::Display for BoardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BoardError::OutOfBounds(pos) => {
+                write!(f, "The position {:?} is out of the board.", pos)
+            }
+            BoardError::NegativeTile(t) => write!(f, "The tile value {} is negative.", t),
+            BoardError::SizeMismatch { expected, found } => write!(
+                f,
+                "Expected a {}x{} board but found {}x{}.",
+                expected.0, expected.1, found.0, found.1
+            ),
+            BoardError::RowCount { expected, found } => {
+                write!(
+                    f,
+                    "Expected {} rows of tiles but found {}.",
+                    expected, found
+                )
+            }
+            BoardError::ColumnCount {
+                row,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Expected {} columns in row {} but found {}.",
+                expected, row, found
+            ),
+            BoardError::ImplausibleTile(pos, t) => {
+                write!(f, "The tile value {} at {:?} is too large.", t, pos)
+            }
+            BoardError::Occupied(pos) => write!(f, "The position {:?} is not blank.", pos),
+        }
+    }
+}
+
+impl error::Error for BoardError {}
+
+/// The error returned by `Core::apply` for a move which is not made.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveError {
+    /// No tile can move or merge in the direction, the board is unchanged.
+    NoChange(Direction),
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoveError::NoChange(direction) => {
+                write!(f, "No tile can move {}.", direction)
+            }
+        }
+    }
+}
+
+impl error::Error for MoveError {}
+
+/// The error of saving and loading, which tells a failed file access from
+/// malformed json and from data that doesn't make a valid board.
+#[derive(Debug)]
+pub enum GameError {
+    Io(io::Error),
+    Serde(serde_json::Error),
+    Validation(String),
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameError::Io(e) => write!(f, "IO error: {}", e),
+            GameError::Serde(e) => write!(f, "Json error: {}", e),
+            GameError::Validation(msg) => write!(f, "Invalid board: {}", msg),
+        }
+    }
+}
+
+impl error::Error for GameError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            GameError::Io(e) => Some(e),
+            GameError::Serde(e) => Some(e),
+            GameError::Validation(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for GameError {
+    fn from(e: io::Error) -> Self {
+        GameError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for GameError {
+    fn from(e: serde_json::Error) -> Self {
+        GameError::Serde(e)
+    }
+}
+
+impl From<BoardError> for GameError {
+    fn from(e: BoardError) -> Self {
+        GameError::Validation(e.to_string())
+    }
+}
+
+/// The error returned when a board can't be parsed from a text grid.
+#[derive(Debug, PartialEq)]
+pub struct ParseBoardError(String);
+
+impl fmt::Display for ParseBoardError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut s = String::from("Situation:\n");
-        for x in 0..self.size {
-            for y in 0..self.size {
-                s.push_str(&format!("{:5}", self.tiles[x][y]));
+        write!(f, "Invalid board: {}", self.0)
+    }
+}
+
+impl error::Error for ParseBoardError {}
+
+/// Parses whitespace-separated rows of stored values, one row per line,
+/// blanks can be written as "0" or ".". The rows must have equal lengths,
+/// but the grid may be rectangular.
+fn parse_grid(s: &str) -> Result<Board, ParseBoardError> {
+    let mut tiles: Vec<Vec<Tile>> = Vec::new();
+    for (x, line) in s
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .enumerate()
+    {
+        let row = line
+            .split_whitespace()
+            .map(|cell| match cell {
+                "." => Ok(BLANK),
+                _ => match cell.parse::<Tile>() {
+                    Ok(t) if t >= 0 => Ok(t),
+                    _ => Err(ParseBoardError(format!("bad tile {:?} in row {}", cell, x))),
+                },
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if let Some(first) = tiles.first() {
+            if first.len() != row.len() {
+                return Err(ParseBoardError(format!(
+                    "expected {} tiles in row {} but found {}",
+                    first.len(),
+                    x,
+                    row.len()
+                )));
             }
-            s.push_str("\n");
         }
-        write!(f, "{}", s)
+        tiles.push(row);
+    }
+    if tiles.is_empty() {
+        return Err(ParseBoardError(String::from("no tiles")));
+    }
+
+    let (rows, cols) = (tiles.len(), tiles[0].len());
+    Ok(Board::new_rect(rows, cols, Some(tiles.concat()), 0))
+}
+
+impl FromStr for Board {
+    type Err = ParseBoardError;
+
+    /// Parses a square grid like `parse_grid`, a rectangular board is only
+    /// built by `Board::new_rect` or shared by `to_share_string`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let board = parse_grid(s)?;
+        if board.rows != board.cols {
+            return Err(ParseBoardError(format!(
+                "expected a square grid but found {} rows of {} tiles",
+                board.rows, board.cols
+            )));
+        }
+        Ok(board)
+    }
+}
+
+impl TryFrom<Vec<Vec<Tile>>> for Board {
+    type Error = BoardError;
+
+    /// Creates a square board of the rows of stored values without scores,
+    /// every row must be as long as the number of rows.
+    fn try_from(tiles: Vec<Vec<Tile>>) -> Result<Self, Self::Error> {
+        let size = tiles.len();
+        if size == 0 {
+            return Err(BoardError::RowCount {
+                expected: 1,
+                found: 0,
+            });
+        }
+        if let Some((row, found)) = tiles
+            .iter()
+            .map(Vec::len)
+            .enumerate()
+            .find(|&(_, len)| len != size)
+        {
+            return Err(BoardError::ColumnCount {
+                row,
+                expected: size,
+                found,
+            });
+        }
+        let board = Board::new(size, Some(tiles.concat()), 0);
+        board.validate()?;
+        Ok(board)
+    }
+}
+
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Situation:\n{}", self.render(true))
     }
 }
 
 impl Board {
+    /// Creates a square board, the tiles are given in row-major order.
     pub fn new(size: usize, tiles: Option<Vec<Tile>>, score: u32) -> Self {
+        Self::new_rect(size, size, tiles, score)
+    }
+
+    /// Creates a blank square board without scores, the counterpart of
+    /// `Board::new(size, None, 0)` which can't be given scores by mistake.
+    pub fn empty(size: usize) -> Self {
+        Self::new(size, None, 0)
+    }
+
+    /// Creates a board with the given rows and columns, the tiles are given in row-major order.
+    pub fn new_rect(rows: usize, cols: usize, tiles: Option<Vec<Tile>>, score: u32) -> Self {
+        debug_assert!(
+            tiles.is_some() || score == 0,
+            "A blank board can't have scores."
+        );
         Board {
-            size,
+            rows,
+            cols,
             tiles: match tiles {
-                Some(data) => data.chunks(size).map(|x| x.to_vec()).collect(),
-                None => vec![vec![BLANK; size]; size],
+                Some(data) => data.chunks(cols).map(|x| x.to_vec()).collect(),
+                None => vec![vec![BLANK; cols]; rows],
             },
             score,
+            moves: 0,
+            highest_merged: BLANK,
+            recording: false,
+            history: Vec::new(),
+            walls: BTreeSet::new(),
         }
     }
 
-    pub fn get(&self, pos: &Coordinate) -> Option<&Tile> {
-        self.tiles.get(pos.0)?.get(pos.1)
+    /// Lists the position, the old and the new stored value of every tile
+    /// that differs in the other board, which must have the same size.
+    pub fn diff(&self, other: &Board) -> Result<Vec<(Coordinate, Tile, Tile)>, BoardError> {
+        if (self.rows, self.cols) != (other.rows, other.cols) {
+            return Err(BoardError::SizeMismatch {
+                expected: (self.rows, self.cols),
+                found: (other.rows, other.cols),
+            });
+        }
+        Ok(self
+            .iter()
+            .zip(other.iter())
+            .filter(|((_, old), (_, new))| old != new)
+            .map(|((pos, old), (_, new))| (pos, old, new))
+            .collect())
     }
 
-    pub fn set(&mut self, pos: &Coordinate, value: Tile) {
-        assert!(value >= 0, "The tile value must be greater then 0.");
-        self.tiles[pos.0][pos.1] = value;
+    /// Clears all tiles and the statistics, but keeps the size.
+    pub fn reset(&mut self) {
+        for row in self.tiles.iter_mut() {
+            row.iter_mut().for_each(|t| *t = BLANK);
+        }
+        self.score = 0;
+        self.moves = 0;
+        self.highest_merged = BLANK;
+        self.history.clear();
     }
 
-    /// Generates multiple values randomly in the given range.
-    pub fn generate(&mut self, times: u32, scope: Range<i32>) {
-        let mut rng = thread_rng();
-        for _ in 0..times {
-            loop {
-                let x = rng.gen_range(0..self.size);
-                let y = rng.gen_range(0..self.size);
-                if self.tiles[x][y] == 0 {
-                    self.tiles[x][y] = rng.gen_range(scope.clone());
-                    break;
-                }
-            }
+    pub fn score(&self) -> u32 {
+        self.score
+    }
+
+    /// Overwrites the scores, e.g. of a state loaded from elsewhere.
+    pub fn set_score(&mut self, score: u32) {
+        self.score = score;
+    }
+
+    /// Returns the board with the scores, for building boards in one
+    /// expression like `Board::empty(4).with_score(128)`.
+    pub fn with_score(mut self, score: u32) -> Self {
+        self.set_score(score);
+        self
+    }
+
+    /// Adds the points to the scores, for the callers applying the scores
+    /// returned by `Core::shift_tiles`. The scores saturate at `u32::MAX`.
+    pub fn add_score(&mut self, points: u32) {
+        self.score = self.score.saturating_add(points);
+    }
+
+    /// Returns the side length of a square board as given to `Board::new`,
+    /// or None for a rectangular board, which has `rows` and `cols`.
+    pub fn size(&self) -> Option<usize> {
+        if self.rows == self.cols {
+            Some(self.rows)
+        } else {
+            None
         }
     }
 
-    fn next(&self, pos: &Coordinate, direction: &Direction) -> Option<Coordinate> {
-        let cell = match direction {
-            Direction::Up if pos.0 >= 1 => (pos.0 - 1, pos.1),
-            Direction::Down if pos.0 + 1 < self.size => (pos.0 + 1, pos.1),
-            Direction::Left if pos.1 >= 1 => (pos.0, pos.1 - 1),
-            Direction::Right if pos.1 + 1 < self.size => (pos.0, pos.1 + 1),
-            _ => return None,
-        };
-        Some(cell)
+    /// Returns the corners in the order top left, top right, bottom left,
+    /// bottom right, which repeat on a board of one row or column.
+    pub fn corners(&self) -> [Coordinate; 4] {
+        let (last_row, last_col) = (self.rows - 1, self.cols - 1);
+        [(0, 0), (0, last_col), (last_row, 0), (last_row, last_col)]
     }
 
-    /// Saves the board data formatted as json to the given path.
-    pub fn save(&self, path: &str) -> Result<(), Box<dyn error::Error>> {
-        let json = serde_json::to_string(&self)?;
-        write(path, json)?;
-        log::debug!("Saved to file: {}", path);
-        Ok(())
+    /// Returns the coordinates on the first or last row or column once each,
+    /// in row-major order.
+    pub fn edge_cells(&self) -> Vec<Coordinate> {
+        self.iter()
+            .map(|(pos, _)| pos)
+            .filter(|&(x, y)| x == 0 || y == 0 || x == self.rows - 1 || y == self.cols - 1)
+            .collect()
     }
-}
 
+    /// Returns the number of rows.
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
 
-pub struct Core;
+    /// Returns the number of columns.
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
 
-impl Core {
-    pub fn new() -> Self {
-        Core {}
+    /// Returns the real values of all tiles in row-major order, 0 for the
+    /// blank tiles, which can be reshaped by `rows` and `cols`. Returns None
+    /// if a tile is beyond 2^63.
+    pub fn as_real_vec(&self) -> Option<Vec<u64>> {
+        self.tiles
+            .iter()
+            .flatten()
+            .map(|&t| real_value(t))
+            .collect()
     }
 
-    pub fn is_game_over(&self, board: &Board) -> bool {
-        let mut queue: VecDeque<Coordinate> = VecDeque::new();
-        queue.push_back((0, 0));
-        while !queue.is_empty() {
-            let current = queue.pop_front().unwrap();
-            if let Some(&BLANK) = board.get(&current) {
-                return false;
-            }
-            for d in [Direction::Right, Direction::Down].iter() {
-                if let Some(next) = board.next(&current, d) {
-                    if board.get(&current) == board.get(&next) {
-                        return false;
-                    }
-                    queue.push_back(next);
-                }
+    /// Returns the stored values of all tiles in row-major order.
+    pub fn as_exponent_vec(&self) -> Vec<Tile> {
+        self.tiles.iter().flatten().copied().collect()
+    }
+
+    /// Returns the highest stored value produced by any merge in this game,
+    /// even if that tile has merged further since, or 0 before the first merge.
+    pub fn highest_merged(&self) -> Tile {
+        self.highest_merged
+    }
+
+    /// Enables or disables recording the directions of the successful moves,
+    /// which is opt-in to save memory. Together with a seeded generator the
+    /// recorded moves fully determine a game.
+    pub fn set_recording(&mut self, enabled: bool) {
+        self.recording = enabled;
+    }
+
+    /// Returns the recorded directions of the successful moves in order.
+    pub fn history(&self) -> &[Direction] {
+        &self.history
+    }
+
+    /// Returns the number of successful moves.
+    pub fn moves(&self) -> u32 {
+        self.moves
+    }
+
+    /// Renders the tiles as an aligned grid, showing the real values
+    /// (1 << stored value) if `real_values` is set, blanks as dots and
+    /// walls as hashes.
+    pub fn render(&self, real_values: bool) -> String {
+        let cells: Vec<Vec<String>> = self
+            .tiles
+            .iter()
+            .enumerate()
+            .map(|(x, row)| {
+                row.iter()
+                    .enumerate()
+                    .map(|(y, &t)| match t {
+                        BLANK if self.is_wall(&(x, y)) => String::from("#"),
+                        BLANK => String::from("."),
+                        _ if real_values => real_label(t),
+                        _ => t.to_string(),
+                    })
+                    .collect()
+            })
+            .collect();
+        let width = cells.iter().flatten().map(|c| c.len()).max().unwrap_or(0) + 1;
+
+        let mut s = String::new();
+        for row in cells.iter() {
+            for cell in row.iter() {
+                s.push_str(&format!("{:>width$}", cell, width = width));
             }
+            s.push('\n');
         }
-        true
+        s
     }
 
-    /// Moves by some direction and returns the traces of all moved tiles.
-    pub fn shift(&self, board: &mut Board, direction: &Direction) -> Vec<Trace> {
-        let mut traces: Vec<Trace> = Vec::new();
+    /// Writes the real values for pasting in chats, one line per row with
+    /// the values separated by a space, "." for a blank and "#" for a wall.
+    /// The scores and moves are not written.
+    pub fn to_share_string(&self) -> String {
+        self.tiles
+            .iter()
+            .enumerate()
+            .map(|(x, row)| {
+                row.iter()
+                    .enumerate()
+                    .map(|(y, &t)| match t {
+                        BLANK if self.is_wall(&(x, y)) => String::from("#"),
+                        BLANK => String::from("."),
+                        _ => real_label(t),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
 
-        match direction {
-            Direction::Right => {
-                for x in 0..board.size {
-                    for y in (0..board.size).rev() {
-                        let mut t = self.do_shift(board, &(x, y), direction);
-                        traces.append(&mut t);
-                    }
-                }
-            }
-            Direction::Down => {
-                for y in 0..board.size {
-                    for x in (0..board.size).rev() {
-                        let mut t = self.do_shift(board, &(x, y), direction);
-                        traces.append(&mut t);
+    /// Parses a board written by `to_share_string`, the real values must be
+    /// powers of two from 2 on. The scores are 0.
+    pub fn from_share_string(s: &str) -> Result<Board, ParseBoardError> {
+        let mut walls = Vec::new();
+        let mut exponents = String::new();
+        for (x, line) in s
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .enumerate()
+        {
+            for (y, cell) in line.split_whitespace().enumerate() {
+                let exponent = match cell {
+                    "." => String::from("."),
+                    "#" => {
+                        walls.push((x, y));
+                        String::from(".")
                     }
-                }
+                    _ => match cell.parse::<u64>() {
+                        Ok(v) if v > 1 && v.is_power_of_two() => v.trailing_zeros().to_string(),
+                        _ => {
+                            return Err(ParseBoardError(format!(
+                                "bad tile {:?} in row {}",
+                                cell, x
+                            )))
+                        }
+                    },
+                };
+                exponents.push_str(&exponent);
+                exponents.push(' ');
             }
-            Direction::Up => {
-                for y in 0..board.size {
-                    for x in 0..board.size {
-                        let mut t = self.do_shift(board, &(x, y), direction);
-                        traces.append(&mut t);
+            exponents.push('\n');
+        }
+
+        let mut board = parse_grid(&exponents)?;
+        for pos in walls.iter() {
+            // The wall positions are on the parsed grid and still blank.
+            board.add_wall(pos).unwrap();
+        }
+        Ok(board)
+    }
+
+    /// Renders the real values like `render`, colored by the stored values
+    /// with ANSI escape codes for terminals, the blanks are dimmed.
+    pub fn render_ansi(&self) -> String {
+        let width = self
+            .iter_nonblank()
+            .map(|(_, t)| real_label(t).len())
+            .max()
+            .unwrap_or(1)
+            + 1;
+
+        let mut s = String::new();
+        for (x, row) in self.tiles.iter().enumerate() {
+            for (y, &t) in row.iter().enumerate() {
+                match t {
+                    BLANK if self.is_wall(&(x, y)) => {
+                        s.push_str(&format!("{:>width$}", "#", width = width))
                     }
-                }
-            }
-            Direction::Left => {
-                for x in 0..board.size {
-                    for y in 0..board.size {
-                        let mut t = self.do_shift(board, &(x, y), direction);
-                        traces.append(&mut t);
+                    BLANK => s.push_str(&format!("\x1b[2m{:>width$}\x1b[0m", ".", width = width)),
+                    _ => {
+                        let color = ANSI_PALETTE[(t as usize).clamp(1, ANSI_PALETTE.len()) - 1];
+                        s.push_str(&format!(
+                            "\x1b[1;38;5;{}m{:>width$}\x1b[0m",
+                            color,
+                            real_label(t),
+                            width = width
+                        ));
                     }
                 }
             }
+            s.push('\n');
         }
-        traces
+        s
     }
 
-    fn do_shift(&self, board: &mut Board, tile: &Coordinate, direction: &Direction) -> Vec<Trace> {
-        let mut tile = tile.clone();
-        let mut no_swapped = false;
-        let mut traces: Vec<Trace> = Vec::new();
-        loop {
-            if let Some(next_tile) = board.next(&tile, &direction) {
-                // debug!("next: {:?} {:?}", next_tile, self.get(&next_tile));
-
-                let tile_val = *board.get(&tile).unwrap();
-                let next_tile_val = *board.get(&next_tile).unwrap();
+    pub fn get(&self, pos: &Coordinate) -> Option<&Tile> {
+        self.tiles.get(pos.0)?.get(pos.1)
+    }
 
-                if tile_val != BLANK {
-                    if next_tile_val == BLANK {
-                        board.set(&next_tile, tile_val);
-                        board.set(&tile, BLANK);
-                        if no_swapped {
-                            let last_tile = traces.pop().unwrap().0;
-                            traces.push((last_tile, next_tile));
-                        } else {
-                            traces.push((tile, next_tile))
-                        }
-                        no_swapped = true;
-                    } else if tile_val == next_tile_val {
-                        board.set(&next_tile, tile_val + 1);
-                        board.set(&tile, BLANK);
-                        board.score += 1 << (tile_val + 1);
-                        traces.push((tile, next_tile));
-                        no_swapped = false;
-                    }
-                }
+    /// Returns the stored values of a row, or None if it is out of the board.
+    pub fn row(&self, r: usize) -> Option<&[Tile]> {
+        self.tiles.get(r).map(Vec::as_slice)
+    }
 
-                tile = next_tile;
-            } else {
-                return traces;
-            }
+    /// Returns the stored values of a column from top to bottom,
+    /// or None if it is out of the board.
+    pub fn column(&self, c: usize) -> Option<Vec<Tile>> {
+        if c >= self.cols {
+            return None;
         }
+        Some(self.tiles.iter().map(|row| row[c]).collect())
     }
-}
 
+    /// Returns the board rotated by 90 degrees clockwise, the rows and the
+    /// columns are swapped, the scores and the moves are copied.
+    pub fn rotate_cw(&self) -> Board {
+        self.transform(self.cols, self.rows, |r, c| (self.rows - 1 - c, r))
+    }
 
-#[cfg(test)]
-mod tests {
-    #![cfg_attr(rustfmt, rustfmt_skip)]
+    /// Returns the board rotated by 90 degrees counterclockwise.
+    pub fn rotate_ccw(&self) -> Board {
+        self.transform(self.cols, self.rows, |r, c| (c, self.cols - 1 - r))
+    }
 
-    use super::*;
+    /// Returns the board mirrored left to right.
+    pub fn flip_horizontal(&self) -> Board {
+        self.transform(self.rows, self.cols, |r, c| (r, self.cols - 1 - c))
+    }
 
-    #[test]
-    fn test_shift() {
-        let core = Core::new();
-        let mut board = Board::new(
-            4,
-            Some(
-                vec![
-                    1, 0, 0, 0,
-                    1, 0, 1, 1,
-                    1, 0, 1, 2,
-                    0, 0, 0, 0,
-                ]
-            ),
-            0
-        );
+    /// Returns the board mirrored top to bottom.
+    pub fn flip_vertical(&self) -> Board {
+        self.transform(self.rows, self.cols, |r, c| (self.rows - 1 - r, c))
+    }
 
-        core.shift(&mut board, &Direction::Right);
-        assert_eq!(
-            board.tiles.concat(),
-            [
-                [0, 0, 0, 1],
-                [0, 0, 1, 2],
-                [0, 0, 0, 3],
-                [0, 0, 0, 0],
-            ].concat()
-        );
+    /// Returns the board with the rows and the columns swapped, i.e.
+    /// mirrored along the main diagonal.
+    pub fn transpose(&self) -> Board {
+        self.transform(self.cols, self.rows, |r, c| (c, r))
+    }
 
-        core.shift(&mut board, &Direction::Down);
-        assert_eq!(
-            board.tiles.concat(),
-            [
-                [0, 0, 0, 0],
-                [0, 0, 0, 1],
-                [0, 0, 0, 2],
-                [0, 0, 1, 3],
-            ].concat()
-        );
+    /// Builds a board of the given shape whose tile (r, c) is the tile at
+    /// `source(r, c)` on this board.
+    fn transform<F>(&self, rows: usize, cols: usize, source: F) -> Board
+    where
+        F: Fn(usize, usize) -> Coordinate,
+    {
+        let tiles = (0..rows)
+            .map(|r| {
+                (0..cols)
+                    .map(|c| {
+                        let (x, y) = source(r, c);
+                        self.tiles[x][y]
+                    })
+                    .collect()
+            })
+            .collect();
+        let walls = (0..rows)
+            .flat_map(|r| (0..cols).map(move |c| (r, c)))
+            .filter(|&(r, c)| self.walls.contains(&source(r, c)))
+            .collect();
+        Board {
+            rows,
+            cols,
+            tiles,
+            score: self.score,
+            moves: self.moves,
+            highest_merged: self.highest_merged,
+            recording: self.recording,
+            history: self.history.clone(),
+            walls,
+        }
     }
 
-    #[test]
-    fn test_game_over() {
-        let core = Core::new();
-        let board = Board::new(
-            4,
-            Some(
-                vec![
-                    1, 2, 3, 4,
-                    4, 3, 2, 1,
-                    1, 2, 3, 4,
-                    4, 3, 2, 1,
-                ]
-            ),
-            0
-        );
-        assert_eq!(core.is_game_over(&board), true);
+    /// Moves the tile from the source of the trace to its destination.
+    pub fn apply_trace(&mut self, trace: &Trace) {
+        let (from, to) = *trace;
+        let value = self.tiles[from.0][from.1];
+        self.set(&to, value);
+        self.set(&from, BLANK);
+    }
 
-        let board = Board::new(
-            4,
-            Some(
-                vec![
-                    1, 2, 3, 4,
-                    4, 3, 2, 1,
-                    1, 2, 3, 4,
-                    4, 3, 2, 4,
-                ]
-            ),
-            0
-        );
-        assert_eq!(core.is_game_over(&board), false);
+    /// Returns the real value (1 << stored value) of a tile, 0 for a blank one,
+    /// or None if the position is out of the board or the tile is beyond 2^63.
+    pub fn tile_real(&self, pos: &Coordinate) -> Option<u64> {
+        self.get(pos).and_then(|&t| real_value(t))
+    }
+
+    /// Turns a blank position into a wall, which tiles can neither pass nor
+    /// merge with and nothing spawns on, e.g. for puzzles.
+    pub fn add_wall(&mut self, pos: &Coordinate) -> Result<(), BoardError> {
+        match self.get(pos) {
+            None => Err(BoardError::OutOfBounds(*pos)),
+            Some(&t) if t != BLANK || self.is_wall(pos) => Err(BoardError::Occupied(*pos)),
+            Some(_) => {
+                self.walls.insert(*pos);
+                Ok(())
+            }
+        }
+    }
+
+    /// Checks whether the position is a wall.
+    pub fn is_wall(&self, pos: &Coordinate) -> bool {
+        self.walls.contains(pos)
+    }
+
+    /// Returns the positions of the walls in row-major order.
+    pub fn walls(&self) -> impl Iterator<Item = &Coordinate> + '_ {
+        self.walls.iter()
+    }
+
+    /// Returns the positions of the blank tiles in row-major order, which
+    /// are the positions a tile can spawn on, the walls are excluded.
+    pub fn empty_cells(&self) -> Vec<Coordinate> {
+        self.iter()
+            .filter(|&(pos, t)| t == BLANK && !self.is_wall(&pos))
+            .map(|(pos, _)| pos)
+            .collect()
     }
 
+    /// Sets the stored value of a tile, panics if the position is out of
+    /// the board, the value is negative or a tile is put on a wall, see
+    /// `try_set` for untrusted input.
+    pub fn set(&mut self, pos: &Coordinate, value: Tile) {
+        assert!(value >= 0, "The tile value must not be negative.");
+        assert!(
+            value == BLANK || !self.is_wall(pos),
+            "A wall can't hold a tile."
+        );
+        self.tiles[pos.0][pos.1] = value;
+    }
+
+    /// Sets the stored value of a tile, or returns an error if the position
+    /// is out of the board or a wall, or the value is negative.
+    pub fn try_set(&mut self, pos: &Coordinate, value: Tile) -> Result<(), BoardError> {
+        if self.get(pos).is_none() {
+            return Err(BoardError::OutOfBounds(*pos));
+        }
+        if self.is_wall(pos) {
+            return Err(BoardError::Occupied(*pos));
+        }
+        if value < 0 {
+            return Err(BoardError::NegativeTile(value));
+        }
+        self.set(pos, value);
+        Ok(())
+    }
+
+    /// Places a tile on a blank position deliberately, the counterpart of
+    /// `generate` for sandboxes and level editors. The scores are untouched.
+    pub fn spawn_at(&mut self, pos: &Coordinate, value: Tile) -> Result<(), BoardError> {
+        match self.get(pos) {
+            None => Err(BoardError::OutOfBounds(*pos)),
+            Some(&t) if t != BLANK || self.is_wall(pos) => Err(BoardError::Occupied(*pos)),
+            Some(_) => self.try_set(pos, value),
+        }
+    }
+
+    /// Places the values on the blank positions in order like `spawn_at`, for
+    /// the scripted scenarios needing no randomness. On an error the placed
+    /// values are removed again, so that the board is left unchanged.
+    pub fn generate_at(&mut self, placements: &[(Coordinate, Tile)]) -> Result<(), BoardError> {
+        for (i, (pos, value)) in placements.iter().enumerate() {
+            if let Err(e) = self.spawn_at(pos, *value) {
+                for (placed, _) in placements[..i].iter() {
+                    self.set(placed, BLANK);
+                }
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Iterates over all tiles with their coordinates in row-major order.
+    pub fn iter(&self) -> impl Iterator<Item = (Coordinate, Tile)> + '_ {
+        self.tiles
+            .iter()
+            .enumerate()
+            .flat_map(|(x, row)| row.iter().enumerate().map(move |(y, &t)| ((x, y), t)))
+    }
+
+    /// Iterates over the non-blank tiles with their coordinates in row-major order.
+    pub fn iter_nonblank(&self) -> impl Iterator<Item = (Coordinate, Tile)> + '_ {
+        self.iter().filter(|&(_, t)| t != BLANK)
+    }
+
+    /// Returns the largest stored value on the board, or 0 for an empty board.
+    pub fn max_tile(&self) -> Tile {
+        self.tiles.iter().flatten().copied().max().unwrap_or(BLANK)
+    }
+
+    /// Returns at most `k` non-blank tiles with their coordinates, the largest
+    /// first, the equal tiles in row-major order.
+    pub fn largest_cells(&self, k: usize) -> Vec<(Coordinate, Tile)> {
+        let mut cells: Vec<_> = self.iter_nonblank().collect();
+        cells.sort_by(|(a_pos, a), (b_pos, b)| b.cmp(a).then(a_pos.cmp(b_pos)));
+        cells.truncate(k);
+        cells
+    }
+
+    /// Returns the sum of the real values of all tiles, independent of the
+    /// scores, which saturates at `u64::MAX` for the huge tiles.
+    pub fn tile_sum(&self) -> u64 {
+        self.iter_nonblank().fold(0u64, |sum, (_, t)| {
+            sum.saturating_add(real_value(t).unwrap_or(u64::MAX))
+        })
+    }
+
+    /// Returns the number of blank tiles, the walls excluded.
+    pub fn count_empty(&self) -> usize {
+        self.iter()
+            .filter(|&(pos, t)| t == BLANK && !self.is_wall(&pos))
+            .count()
+    }
+
+    /// Hashes the size, the tiles and the walls by FNV-1a for transposition
+    /// tables, the scores and moves are ignored. Unlike `Hash` with the std
+    /// hashers, the value is the same across runs and platforms.
+    pub fn fingerprint(&self) -> u64 {
+        let sizes = [self.rows as u64, self.cols as u64];
+        let bytes = sizes
+            .iter()
+            .flat_map(|n| n.to_le_bytes())
+            .chain(self.tiles.iter().flatten().flat_map(|t| t.to_le_bytes()))
+            .chain(
+                self.walls
+                    .iter()
+                    .flat_map(|&(x, y)| [x as u64, y as u64])
+                    .flat_map(|n| n.to_le_bytes()),
+            );
+        bytes.fold(FNV_OFFSET, |hash, b| {
+            (hash ^ b as u64).wrapping_mul(FNV_PRIME)
+        })
+    }
+
+    /// Checks whether every tile is blank.
+    pub fn is_empty(&self) -> bool {
+        self.tiles.iter().flatten().all(|&t| t == BLANK)
+    }
+
+    /// Checks whether there is no blank tile, so that nothing can spawn.
+    pub fn is_full(&self) -> bool {
+        self.count_empty() == 0
+    }
+
+    /// Generates multiple values randomly in the given range and returns
+    /// the spawned tiles, which are fewer than `times` if the board is full.
+    pub fn generate(&mut self, times: u32, scope: Range<i32>) -> Vec<(Coordinate, Tile)> {
+        self.generate_with(&mut thread_rng(), times, scope)
+    }
+
+    /// Starts a new game like the classic 2048, which begins with two tiles:
+    /// clears the board and spawns `count` tiles in the given range.
+    pub fn init_spawn(&mut self, count: u32, scope: Range<i32>) -> Vec<(Coordinate, Tile)> {
+        self.reset();
+        self.generate(count, scope)
+    }
+
+    /// Generates multiple values like `generate_weighted` using the given
+    /// random number generator, e.g. a seeded one for reproducible spawns.
+    pub fn generate_weighted_with<R: Rng>(
+        &mut self,
+        rng: &mut R,
+        times: u32,
+        weights: &[(Tile, f64)],
+    ) -> Vec<(Coordinate, Tile)> {
+        assert!(
+            weights.iter().all(|&(_, w)| w >= 0.0) && weights.iter().any(|&(_, w)| w > 0.0),
+            "The weights must not be negative and at least one must be positive."
+        );
+        let index = WeightedIndex::new(weights.iter().map(|&(_, w)| w)).unwrap();
+        self.spawn(rng, times, |rng| weights[index.sample(rng)].0, &mut ())
+    }
+
+    /// Generates multiple values in the given range using the given random
+    /// number generator, so that a seeded generator yields reproducible spawns.
+    pub fn generate_with<R: Rng>(
+        &mut self,
+        rng: &mut R,
+        times: u32,
+        scope: Range<i32>,
+    ) -> Vec<(Coordinate, Tile)> {
+        self.spawn(rng, times, |rng| rng.gen_range(scope.clone()), &mut ())
+    }
+
+    /// Generates multiple values sampled by the given weights of stored
+    /// values, e.g. `[(1, 0.9), (2, 0.1)]` for the classic 2s and 4s.
+    /// Panics if any weight is negative or no weight is positive.
+    pub fn generate_weighted(
+        &mut self,
+        times: u32,
+        weights: &[(Tile, f64)],
+    ) -> Vec<(Coordinate, Tile)> {
+        self.generate_weighted_with(&mut thread_rng(), times, weights)
+    }
+
+    /// Generates multiple values randomly in the given range on the blank
+    /// tiles chosen by the policy, e.g. to keep the spawns away from large
+    /// tiles in a tutorial. The policy is given the board and all blank
+    /// positions and must return one of them, `generate` picks uniformly.
+    pub fn generate_with_policy<P>(
+        &mut self,
+        times: u32,
+        scope: Range<i32>,
+        mut policy: P,
+    ) -> Vec<(Coordinate, Tile)>
+    where
+        P: FnMut(&Board, &[Coordinate]) -> Coordinate,
+    {
+        let mut rng = thread_rng();
+        let mut spawns = Vec::new();
+        for _ in 0..times {
+            let candidates = self.empty_cells();
+            if candidates.is_empty() {
+                break;
+            }
+            let pos = policy(self, &candidates);
+            assert!(
+                candidates.contains(&pos),
+                "The policy must choose a blank tile."
+            );
+            let value = rng.gen_range(scope.clone());
+            self.set(&pos, value);
+            spawns.push((pos, value));
+        }
+        spawns
+    }
+
+    /// Generates multiple values randomly in the given range and reports
+    /// each spawned tile to the observer.
+    pub fn generate_observed<O: GameObserver>(
+        &mut self,
+        times: u32,
+        scope: Range<i32>,
+        observer: &mut O,
+    ) -> Vec<(Coordinate, Tile)> {
+        self.spawn(
+            &mut thread_rng(),
+            times,
+            |rng| rng.gen_range(scope.clone()),
+            observer,
+        )
+    }
+
+    fn spawn<R, F, O>(
+        &mut self,
+        rng: &mut R,
+        times: u32,
+        mut value: F,
+        observer: &mut O,
+    ) -> Vec<(Coordinate, Tile)>
+    where
+        R: Rng,
+        F: FnMut(&mut R) -> Tile,
+        O: GameObserver,
+    {
+        if self.is_full() {
+            return Vec::new();
+        }
+        let times = times.min(self.count_empty() as u32);
+        let mut spawns = Vec::new();
+        for _ in 0..times {
+            loop {
+                let x = rng.gen_range(0..self.rows);
+                let y = rng.gen_range(0..self.cols);
+                if self.tiles[x][y] == BLANK && !self.is_wall(&(x, y)) {
+                    self.tiles[x][y] = value(rng);
+                    observer.on_spawn((x, y), self.tiles[x][y]);
+                    spawns.push(((x, y), self.tiles[x][y]));
+                    break;
+                }
+            }
+        }
+        spawns
+    }
+
+    /// Returns the rows or the columns along the direction, each ordered
+    /// from the edge the tiles move towards. The walls split them into
+    /// separate lines like the edges of the board.
+    fn lines(&self, direction: &Direction) -> Vec<Vec<Coordinate>> {
+        let (rows, cols) = (self.rows, self.cols);
+        let lines: Vec<Vec<Coordinate>> = match direction {
+            Direction::Right => (0..rows)
+                .map(|x| (0..cols).rev().map(|y| (x, y)).collect())
+                .collect(),
+            Direction::Down => (0..cols)
+                .map(|y| (0..rows).rev().map(|x| (x, y)).collect())
+                .collect(),
+            Direction::Up => (0..cols)
+                .map(|y| (0..rows).map(|x| (x, y)).collect())
+                .collect(),
+            Direction::Left => (0..rows)
+                .map(|x| (0..cols).map(|y| (x, y)).collect())
+                .collect(),
+        };
+        if self.walls.is_empty() {
+            return lines;
+        }
+        lines
+            .iter()
+            .flat_map(|line| line.split(|pos| self.is_wall(pos)))
+            .map(<[Coordinate]>::to_vec)
+            .collect()
+    }
+
+    /// Shifts the board like `Core::shift` and returns whether any tile
+    /// moved, for the callers which don't need the traces.
+    pub fn apply_direction(&mut self, core: &Core, direction: &Direction) -> bool {
+        !core.shift(self, direction).is_empty()
+    }
+
+    /// Returns the board after shifting in the direction without spawning
+    /// with the gained scores, or None if no tile moves. Unlike
+    /// `Core::preview`, a no-op is told apart, and the board is untouched.
+    pub fn preview_move(&self, core: &Core, direction: &Direction) -> Option<(Board, u32)> {
+        let mut next = self.clone();
+        let result = core.shift(&mut next, direction);
+        (!result.is_empty()).then_some((next, result.gained))
+    }
+
+    /// Returns the adjacent coordinates on the board in the order up, down,
+    /// left, right, the walls are left out since no tile passes them.
+    pub fn neighbors(&self, pos: &Coordinate) -> Vec<Coordinate> {
+        Direction::all()
+            .iter()
+            .filter_map(|d| self.next(pos, d))
+            .collect()
+    }
+
+    fn next(&self, pos: &Coordinate, direction: &Direction) -> Option<Coordinate> {
+        let cell = match direction {
+            Direction::Up if pos.0 >= 1 => (pos.0 - 1, pos.1),
+            Direction::Down if pos.0 + 1 < self.rows => (pos.0 + 1, pos.1),
+            Direction::Left if pos.1 >= 1 => (pos.0, pos.1 - 1),
+            Direction::Right if pos.1 + 1 < self.cols => (pos.0, pos.1 + 1),
+            _ => return None,
+        };
+        if self.is_wall(&cell) {
+            return None;
+        }
+        Some(cell)
+    }
+
+    /// Saves the board data formatted as json to the given path.
+    pub fn save(&self, path: &str) -> Result<(), GameError> {
+        let json = serde_json::to_string(&self)?;
+        write(path, json)?;
+        log::debug!("Saved to file: {}", path);
+        Ok(())
+    }
+
+    /// Encodes the board compactly: a magic header, the rows and columns as
+    /// u16, the scores and moves as u32, then one byte per stored value and
+    /// a trailing byte of the highest merged value, all integers are
+    /// little-endian. The recorded moves and the walls are only kept by the
+    /// json formats.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, GameError> {
+        let too_large = |_| GameError::Validation("The board is too large to encode.".to_string());
+        let rows = u16::try_from(self.rows).map_err(too_large)?;
+        let cols = u16::try_from(self.cols).map_err(too_large)?;
+        let mut bytes = Vec::with_capacity(BIN_HEADER_LEN + self.rows * self.cols);
+        bytes.extend_from_slice(BIN_MAGIC);
+        bytes.extend_from_slice(&rows.to_le_bytes());
+        bytes.extend_from_slice(&cols.to_le_bytes());
+        bytes.extend_from_slice(&self.score.to_le_bytes());
+        bytes.extend_from_slice(&self.moves.to_le_bytes());
+        for &t in self
+            .tiles
+            .iter()
+            .flatten()
+            .chain(Some(&self.highest_merged))
+        {
+            bytes.push(u8::try_from(t).map_err(|_| {
+                GameError::Validation(format!("The tile value {} doesn't fit in a byte.", t))
+            })?);
+        }
+        Ok(bytes)
+    }
+
+    /// Decodes a board encoded by `to_bytes`, the trailing byte of the highest
+    /// merged value is optional for the files saved before it was added.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Board, GameError> {
+        if bytes.len() < BIN_HEADER_LEN || &bytes[..BIN_MAGIC.len()] != BIN_MAGIC {
+            return Err(GameError::Validation(
+                "Not a binary board file.".to_string(),
+            ));
+        }
+        let u16_at = |i: usize| u16::from_le_bytes([bytes[i], bytes[i + 1]]);
+        let u32_at =
+            |i: usize| u32::from_le_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]]);
+        let rows = u16_at(4) as usize;
+        let cols = u16_at(6) as usize;
+        let (tiles, highest_merged) = match &bytes[BIN_HEADER_LEN..] {
+            rest if rest.len() == rows * cols + 1 => (&rest[..rows * cols], rest[rows * cols]),
+            rest => (rest, 0),
+        };
+        if tiles.len() != rows * cols {
+            return Err(GameError::Validation(format!(
+                "Expected {} tiles but found {}.",
+                rows * cols,
+                tiles.len()
+            )));
+        }
+
+        let mut board = Board::new_rect(
+            rows,
+            cols,
+            Some(tiles.iter().map(|&t| Tile::from(t)).collect()),
+            u32_at(8),
+        );
+        board.moves = u32_at(12);
+        board.highest_merged = Tile::from(highest_merged);
+        board.validate()?;
+        Ok(board)
+    }
+
+    /// Saves the board data encoded by `to_bytes` to the given path.
+    pub fn save_bin(&self, path: &str) -> Result<(), GameError> {
+        write(path, self.to_bytes()?)?;
+        log::debug!("Saved to file: {}", path);
+        Ok(())
+    }
+
+    /// Loads the board data encoded by `to_bytes` from the given path.
+    pub fn load_bin(path: &str) -> Result<Board, GameError> {
+        let board = Self::from_bytes(&read(path)?)?;
+        log::debug!("Loaded from file: {}", path);
+        Ok(board)
+    }
+
+    /// Loads the board data formatted as json from the given path.
+    pub fn load(path: &str) -> Result<Board, GameError> {
+        let json = read_to_string(path)?;
+        let board: Board = serde_json::from_str(&json)?;
+        board.validate()?;
+        log::debug!("Loaded from file: {}", path);
+        Ok(board)
+    }
+
+    /// Formats the board as json like `save`, but stores the real values
+    /// (2, 4, 8...) instead of exponents, with 0 for blanks. A tile beyond
+    /// 2^63 is an error.
+    pub fn to_json_real(&self) -> Result<String, GameError> {
+        let tiles = self
+            .tiles
+            .iter()
+            .enumerate()
+            .map(|(x, row)| {
+                row.iter()
+                    .enumerate()
+                    .map(|(y, &t)| real_value(t).ok_or(BoardError::ImplausibleTile((x, y), t)))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let board = RealBoard {
+            rows: self.rows,
+            cols: self.cols,
+            tiles,
+            score: self.score,
+            moves: self.moves,
+            highest_merged: self.highest_merged,
+            recording: self.recording,
+            history: self.history.clone(),
+            walls: self.walls.clone(),
+        };
+        Ok(serde_json::to_string(&board)?)
+    }
+
+    /// Parses the json formatted by `to_json_real`, rejecting values which
+    /// are neither 0 nor a power of two greater than 1.
+    pub fn from_json_real(json: &str) -> Result<Board, GameError> {
+        let real: RealBoard = serde_json::from_str(json)?;
+        let mut tiles = Vec::with_capacity(real.tiles.len());
+        for row in real.tiles.iter() {
+            let mut exponents = Vec::with_capacity(row.len());
+            for &value in row.iter() {
+                exponents.push(match value {
+                    0 => BLANK,
+                    v if v > 1 && v.is_power_of_two() => v.trailing_zeros() as Tile,
+                    v => {
+                        return Err(GameError::Validation(format!(
+                            "The tile value {} is not a power of two.",
+                            v
+                        )))
+                    }
+                });
+            }
+            tiles.push(exponents);
+        }
+
+        let board = Board {
+            rows: real.rows,
+            cols: real.cols,
+            tiles,
+            score: real.score,
+            moves: real.moves,
+            highest_merged: real.highest_merged,
+            recording: real.recording,
+            history: real.history,
+            walls: real.walls,
+        };
+        board.validate()?;
+        Ok(board)
+    }
+
+    /// Checks the integrity of a board from an external source: the tiles
+    /// must be `rows` x `cols`, and every value must be a blank or an exponent
+    /// whose real value fits in u64.
+    pub fn validate(&self) -> Result<(), BoardError> {
+        if self.tiles.len() != self.rows {
+            return Err(BoardError::RowCount {
+                expected: self.rows,
+                found: self.tiles.len(),
+            });
+        }
+        for (x, row) in self.tiles.iter().enumerate() {
+            if row.len() != self.cols {
+                return Err(BoardError::ColumnCount {
+                    row: x,
+                    expected: self.cols,
+                    found: row.len(),
+                });
+            }
+        }
+        for (pos, tile) in self.iter() {
+            if tile < 0 {
+                return Err(BoardError::NegativeTile(tile));
+            }
+            if tile > MAX_EXPONENT {
+                return Err(BoardError::ImplausibleTile(pos, tile));
+            }
+        }
+        for pos in self.walls.iter() {
+            match self.get(pos) {
+                None => return Err(BoardError::OutOfBounds(*pos)),
+                Some(&t) if t != BLANK => return Err(BoardError::Occupied(*pos)),
+                Some(_) => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The result of a shift contains:
+///     1.the traces of all moved tiles
+///     2.the coordinates of the tiles produced by merges
+///     3.the scores gained by the merges
+///     4.the stored values of the moving and the resident tile of each merge
+///     5.the highest merged value of the board before the shift.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct MoveResult {
+    pub traces: Vec<Trace>,
+    pub merges: Vec<Coordinate>,
+    pub gained: u32,
+    pub merged_values: Vec<(Tile, Tile)>,
+    pub previous_highest_merged: Tile,
+}
+
+impl MoveResult {
+    /// Checks whether no tile was moved, i.e. the shift was invalid.
+    pub fn is_empty(&self) -> bool {
+        self.traces.is_empty()
+    }
+
+    /// Records a step of a tile, which extends its last trace if the tile
+    /// has moved before, so that every moved tile has exactly one trace.
+    fn push_step(&mut self, from: Coordinate, to: Coordinate, continued: bool) {
+        if continued {
+            let origin = self.traces.pop().unwrap().0;
+            self.traces.push((origin, to));
+        } else {
+            self.traces.push((from, to));
+        }
+    }
+}
+
+/// The result of a turn of `Core::play_turn` contains:
+///     1.the traces of all moved tiles
+///     2.the positions and the stored values of the spawned tiles
+///     3.the scores gained by the move
+///     4.the state of the game after the spawns.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TurnResult {
+    pub traces: Vec<Trace>,
+    pub spawns: Vec<(Coordinate, Tile)>,
+    pub gained: u32,
+    pub state: GameState,
+}
+
+/// The state of a game, the game is lost once no move is possible,
+/// even if the target was reached before.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameState {
+    Playing,
+    Won,
+    Lost,
+}
+
+/// When `Core::is_game_over` ends the game.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum GameOverPolicy {
+    /// The game is over once no move changes the board, as in the classic 2048.
+    #[default]
+    NoMovesLeft,
+    /// The game is over once no tile is blank, even if tiles could merge.
+    BoardFull,
+}
+
+/// Receives the events of a game, all methods do nothing by default.
+pub trait GameObserver {
+    /// Called when two tiles merged into a new value at the given position.
+    fn on_merge(&mut self, _at: Coordinate, _new_value: Tile) {}
+
+    /// Called when a new tile is spawned at the given position.
+    fn on_spawn(&mut self, _at: Coordinate, _value: Tile) {}
+
+    /// Called when a tile moved from the source to the destination.
+    fn on_move(&mut self, _trace: Trace) {}
+}
+
+/// The observer ignoring all events.
+impl GameObserver for () {}
+
+pub struct Core {
+    rule: Box<dyn MergeRule>,
+    policy: GameOverPolicy,
+    target: Tile,
+}
+
+impl Default for Core {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Core {
+    /// Creates a core with the classic merge rule.
+    pub fn new() -> Self {
+        Self::with_rule(Box::new(ClassicRule))
+    }
+
+    pub fn with_rule(rule: Box<dyn MergeRule>) -> Self {
+        Core {
+            rule,
+            policy: GameOverPolicy::default(),
+            target: TARGET,
+        }
+    }
+
+    /// Returns the core whose turns are won by the exponent instead of the
+    /// 2048 `TARGET`.
+    pub fn with_target(mut self, target: Tile) -> Self {
+        self.target = target;
+        self
+    }
+
+    pub fn target(&self) -> Tile {
+        self.target
+    }
+
+    /// Returns the core ending the game by the policy instead of the
+    /// default `GameOverPolicy::NoMovesLeft`.
+    pub fn with_policy(mut self, policy: GameOverPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn policy(&self) -> GameOverPolicy {
+        self.policy
+    }
+
+    /// Checks whether the game is over by the policy. For the default
+    /// `NoMovesLeft` every tile is checked once, the game is over if there
+    /// is neither a blank tile nor a tile mergeable with its right or down
+    /// neighbor.
+    pub fn is_game_over(&self, board: &Board) -> bool {
+        if self.policy == GameOverPolicy::BoardFull {
+            return board.is_full();
+        }
+        for (current, tile) in board.iter() {
+            if board.is_wall(&current) {
+                continue;
+            }
+            if tile == BLANK {
+                return false;
+            }
+            for d in [Direction::Right, Direction::Down].iter() {
+                if let Some(next) = board.next(&current, d) {
+                    let next_tile = *board.get(&next).unwrap();
+                    if self.rule.can_merge(tile, next_tile) || self.rule.can_merge(next_tile, tile)
+                    {
+                        return false;
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    /// Returns the scores of merging two tiles of the given stored value,
+    /// which is the real value of the merged tile as in the classic 2048,
+    /// e.g. merging two 2s (exponent 1) into a 4 gains 4, and saturates at
+    /// `u32::MAX` for the huge tiles of an endless game.
+    pub fn merge_points(exponent: Tile) -> u32 {
+        // Computed in u64 and clamped, so that a pathological exponent
+        // saturates at u32::MAX instead of overflowing the shift.
+        let points = 1u64 << (exponent.clamp(0, 62) + 1);
+        points.min(u32::MAX as u64) as u32
+    }
+
+    /// Checks whether any tile on the board reaches the target exponent,
+    /// e.g. 11 for the classic 2048 tile, or goes beyond it.
+    pub fn has_won(&self, board: &Board, target_exponent: Tile) -> bool {
+        board.tiles.iter().flatten().any(|&t| t >= target_exponent)
+    }
+
+    /// Checks whether shifting in the direction would change the board, i.e.
+    /// some tile has a blank or a mergeable tile next to it in that direction.
+    pub fn can_move(&self, board: &Board, direction: &Direction) -> bool {
+        board.iter_nonblank().any(|(pos, tile)| {
+            board.next(&pos, direction).is_some_and(|next| {
+                matches!(board.get(&next), Some(&t) if t == BLANK || self.rule.can_merge(tile, t))
+            })
+        })
+    }
+
+    /// Returns the positions of the tiles which can neither slide nor merge
+    /// in any direction, the per-tile version of `is_game_over`.
+    pub fn locked_cells(&self, board: &Board) -> Vec<Coordinate> {
+        board
+            .iter_nonblank()
+            .filter(|&(pos, tile)| {
+                board.neighbors(&pos).iter().all(|next| {
+                    let t = *board.get(next).unwrap();
+                    t != BLANK && !self.rule.can_merge(tile, t)
+                })
+            })
+            .map(|(pos, _)| pos)
+            .collect()
+    }
+
+    /// Returns the directions which would change the board.
+    pub fn available_moves(&self, board: &Board) -> Vec<Direction> {
+        Direction::all()
+            .iter()
+            .filter(|d| self.can_move(board, d))
+            .copied()
+            .collect()
+    }
+
+    /// Returns the board after shifting in the direction without spawning,
+    /// the given board is left untouched.
+    pub fn preview(&self, board: &Board, direction: &Direction) -> Board {
+        let mut next = board.clone();
+        self.shift(&mut next, direction);
+        next
+    }
+
+    /// Returns the scores the move would gain, without touching the board.
+    pub fn score_gain(&self, board: &Board, direction: &Direction) -> u32 {
+        self.shift(&mut board.clone(), direction).gained
+    }
+
+    /// Returns the number of merges the move would produce, without touching
+    /// the board.
+    pub fn count_merges(&self, board: &Board, direction: &Direction) -> usize {
+        self.shift(&mut board.clone(), direction).merges.len()
+    }
+
+    /// Suggests the direction that gains the most scores immediately,
+    /// ties are broken by the most blank tiles left and then by the order
+    /// Up, Down, Left, Right. Returns None if no direction moves any tile.
+    pub fn hint(&self, board: &Board) -> Option<Direction> {
+        let mut best: Option<(Direction, (u32, usize))> = None;
+        for direction in Direction::all().iter() {
+            let next = self.preview(board, direction);
+            if next == *board {
+                continue;
+            }
+            let key = (next.score - board.score, next.count_empty());
+            if best.is_none_or(|(_, k)| key > k) {
+                best = Some((*direction, key));
+            }
+        }
+        best.map(|(direction, _)| direction)
+    }
+
+    /// Returns the state of the game for the given target exponent.
+    pub fn state(&self, board: &Board, target_exponent: Tile) -> GameState {
+        if self.is_game_over(board) {
+            GameState::Lost
+        } else if self.has_won(board, target_exponent) {
+            GameState::Won
+        } else {
+            GameState::Playing
+        }
+    }
+
+    /// Moves by some direction and returns the traces of all moved tiles,
+    /// the merged tiles and the gained scores.
+    ///
+    /// The traces are in the order the tiles are processed, the same for all
+    /// directions: the rows for Left and Right and the columns for Up and
+    /// Down from the lowest index on, and within a line the tile nearest to
+    /// the edge the tiles move towards first, e.g. from the rightmost tile on
+    /// for Right. So a tile is always traced after the tiles ahead of it, and
+    /// the merges are in the order of their traces.
+    pub fn shift(&self, board: &mut Board, direction: &Direction) -> MoveResult {
+        self.shift_observed(board, direction, &mut ())
+    }
+
+    /// Moves like `shift` and also counts the merges of the move by the
+    /// stored value of the merged tile, for the analytics of the scores.
+    pub fn shift_detailed(
+        &self,
+        board: &mut Board,
+        direction: &Direction,
+    ) -> (MoveResult, HashMap<Tile, u32>) {
+        let result = self.shift(board, direction);
+        let mut counts = HashMap::new();
+        for &(moving, resident) in result.merged_values.iter() {
+            *counts
+                .entry(self.rule.merged(moving, resident))
+                .or_insert(0) += 1;
+        }
+        (result, counts)
+    }
+
+    /// Moves like `shift` and reports the events to the observer, all moves
+    /// are reported in the order of the traces, followed by all merges.
+    pub fn shift_observed<O: GameObserver>(
+        &self,
+        board: &mut Board,
+        direction: &Direction,
+        observer: &mut O,
+    ) -> MoveResult {
+        let result = self.shift_result(board, direction);
+        board.add_score(result.gained);
+        if !result.is_empty() {
+            board.moves += 1;
+            if board.recording {
+                board.history.push(*direction);
+            }
+        }
+
+        for trace in result.traces.iter() {
+            observer.on_move(*trace);
+        }
+        for pos in result.merges.iter() {
+            observer.on_merge(*pos, *board.get(pos).unwrap());
+        }
+        result
+    }
+
+    /// Moves the tiles by some direction and returns the traces with the
+    /// gained scores, leaving the scores and the moves of the board to the
+    /// caller, e.g. by `Board::add_score`.
+    pub fn shift_tiles(&self, board: &mut Board, direction: &Direction) -> (Vec<Trace>, u32) {
+        let result = self.shift_result(board, direction);
+        (result.traces, result.gained)
+    }
+
+    /// Slides the tiles by some direction without merging, so that no blank
+    /// is left between the tiles of a line, and returns the traces of the
+    /// moved tiles. The scores and the moves are untouched, it is the slide
+    /// phase of a shift for animations and the variants without merges.
+    pub fn compact(&self, board: &mut Board, direction: &Direction) -> Vec<Trace> {
+        let mut traces = Vec::new();
+        for line in board.lines(direction).iter() {
+            let mut at = 0;
+            for &pos in line.iter() {
+                let tile_val = *board.get(&pos).unwrap();
+                if tile_val == BLANK {
+                    continue;
+                }
+                if line[at] != pos {
+                    board.set(&line[at], tile_val);
+                    board.set(&pos, BLANK);
+                    traces.push((pos, line[at]));
+                }
+                at += 1;
+            }
+        }
+        traces
+    }
+
+    /// Moves the tiles only, the scores are accumulated in the result.
+    fn shift_result(&self, board: &mut Board, direction: &Direction) -> MoveResult {
+        let mut result = MoveResult {
+            previous_highest_merged: board.highest_merged,
+            ..MoveResult::default()
+        };
+
+        for line in board.lines(direction).iter() {
+            self.shift_line(board, line, &mut result, None);
+        }
+        result
+    }
+
+    /// Returns the boards after every single step of a shift for animations,
+    /// in each step one tile slides to the next position or merges, the given
+    /// board is not included. The last board is the same as after `shift`,
+    /// including the scores and moves, and no board is returned for an
+    /// invalid move.
+    pub fn shift_steps(&self, board: &Board, direction: &Direction) -> Vec<Board> {
+        let mut current = board.clone();
+        let mut result = MoveResult::default();
+        let mut frames = Vec::new();
+        for line in current.lines(direction).iter() {
+            self.shift_line(&mut current, line, &mut result, Some(&mut frames));
+        }
+        if let Some(last) = frames.last_mut() {
+            *last = board.clone();
+            self.shift(last, direction);
+        }
+        frames
+    }
+
+    /// Plays a whole turn: shifts by the direction and, only if any tile
+    /// moved, spawns one tile in the scope and returns the result with the
+    /// state for the target of the core. Returns None for an invalid move, so the
+    /// caller knows the input is not consumed.
+    pub fn play_turn(
+        &self,
+        board: &mut Board,
+        direction: &Direction,
+        spawn_scope: Range<i32>,
+    ) -> Option<TurnResult> {
+        self.play_turn_with_spawns(board, direction, spawn_scope, 1)
+    }
+
+    /// Plays a turn like `play_turn` spawning `spawn_count` tiles, e.g.
+    /// `GameConfig::spawns_for_size` on a large board. Fewer tiles spawn if
+    /// the blank tiles run out, a count of 0 plays a turn without spawns.
+    pub fn play_turn_with_spawns(
+        &self,
+        board: &mut Board,
+        direction: &Direction,
+        spawn_scope: Range<i32>,
+        spawn_count: u32,
+    ) -> Option<TurnResult> {
+        self.play_turn_by(board, direction, self.target, |board| {
+            board.generate(spawn_count, spawn_scope)
+        })
+    }
+
+    /// Shifts and, only if any tile moved, spawns by the closure and returns
+    /// the result with the state for the target.
+    pub(crate) fn play_turn_by<F>(
+        &self,
+        board: &mut Board,
+        direction: &Direction,
+        target: Tile,
+        spawn: F,
+    ) -> Option<TurnResult>
+    where
+        F: FnOnce(&mut Board) -> Vec<(Coordinate, Tile)>,
+    {
+        let result = self.shift(board, direction);
+        if result.is_empty() {
+            return None;
+        }
+        let spawns = spawn(board);
+        Some(TurnResult {
+            traces: result.traces,
+            spawns,
+            gained: result.gained,
+            state: self.state(board, target),
+        })
+    }
+
+    /// Plays a classic turn like `play_turn` spawning a 2 or a 4, it never
+    /// blocks on any input, so an event loop or a server can drive the game
+    /// by calling it once per received direction.
+    pub fn step(&self, board: &mut Board, direction: &Direction) -> Option<TurnResult> {
+        self.play_turn(board, direction, 1..3)
+    }
+
+    /// Swaps the sources and destinations of the traces in reverse order,
+    /// so that applying them one by one moves the tiles back.
+    pub fn invert_traces(&self, traces: &[Trace]) -> Vec<Trace> {
+        traces.iter().rev().map(|&(from, to)| (to, from)).collect()
+    }
+
+    /// Restores the board to the situation before the shift which returned
+    /// the result. A merged tile is listed in the merges and is the
+    /// destination of the trace of the tile which moved into it, which is
+    /// the last trace to that tile, so inverting that trace splits the tile
+    /// back into the recorded values while the other traces move the tiles
+    /// back as they are.
+    pub fn rollback(&self, board: &mut Board, result: &MoveResult) {
+        let mut merges: Vec<_> = result
+            .merges
+            .iter()
+            .zip(result.merged_values.iter())
+            .collect();
+        for trace in self.invert_traces(&result.traces).iter() {
+            let (from, to) = *trace;
+            match merges.iter().position(|&(&pos, _)| pos == from) {
+                Some(i) => {
+                    let (_, &(moving, resident)) = merges.remove(i);
+                    board.set(&to, moving);
+                    board.set(&from, resident);
+                }
+                None => board.apply_trace(trace),
+            }
+        }
+        board.score = board.score.saturating_sub(result.gained);
+        board.highest_merged = result.previous_highest_merged;
+        if !result.is_empty() {
+            // A board counting from 0 again, e.g. loaded without the moves,
+            // stays at 0 instead of underflowing.
+            board.moves = board.moves.saturating_sub(1);
+            if board.recording {
+                board.history.pop();
+            }
+        }
+    }
+
+    /// Shifts like `shift`, but an invalid move is an error instead of an
+    /// empty result, so that the caller just matches on it.
+    pub fn apply(&self, board: &mut Board, direction: &Direction) -> Result<MoveResult, MoveError> {
+        let result = self.shift(board, direction);
+        if result.is_empty() {
+            return Err(MoveError::NoChange(*direction));
+        }
+        Ok(result)
+    }
+
+    /// Applies the moves in order and returns the result of each move.
+    /// Invalid moves leave the board untouched and are not counted, their
+    /// results are empty so that the results still line up with the moves.
+    pub fn replay(&self, board: &mut Board, moves: &[Direction]) -> Vec<MoveResult> {
+        moves.iter().map(|d| self.shift(board, d)).collect()
+    }
+
+    /// Replays the moves like `replay`, placing the next recorded spawn after
+    /// every valid move instead of a random one, so a shared game plays out
+    /// the same whatever the RNG is. The moves after the spawns run out get
+    /// no spawn. Returns the error of a spawn onto an occupied or missing
+    /// tile, the board is then left as it is after the failing move.
+    pub fn replay_with_spawns(
+        &self,
+        board: &mut Board,
+        moves: &[Direction],
+        spawns: &[(Coordinate, Tile)],
+    ) -> Result<Vec<MoveResult>, BoardError> {
+        let mut spawns = spawns.iter();
+        let mut results = Vec::with_capacity(moves.len());
+        for direction in moves.iter() {
+            let result = self.shift(board, direction);
+            if !result.is_empty() {
+                if let Some((pos, value)) = spawns.next() {
+                    board.spawn_at(pos, *value)?;
+                }
+            }
+            results.push(result);
+        }
+        Ok(results)
+    }
+
+    /// Shifts the tiles of a line towards its first coordinate, the line is
+    /// in the movement order so that the tiles ahead are settled first.
+    /// Every single step is pushed to the frames if they are given.
+    fn shift_line(
+        &self,
+        board: &mut Board,
+        line: &[Coordinate],
+        result: &mut MoveResult,
+        mut frames: Option<&mut Vec<Board>>,
+    ) {
+        for i in 1..line.len() {
+            let tile_val = *board.get(&line[i]).unwrap();
+            if tile_val == BLANK {
+                continue;
+            }
+            let mut continued = false;
+            for at in (1..=i).rev() {
+                let (tile, next_tile) = (line[at], line[at - 1]);
+                let next_tile_val = *board.get(&next_tile).unwrap();
+                if next_tile_val == BLANK {
+                    board.set(&next_tile, tile_val);
+                    board.set(&tile, BLANK);
+                    result.push_step(tile, next_tile, continued);
+                    if let Some(frames) = frames.as_mut() {
+                        frames.push(board.clone());
+                    }
+                    continued = true;
+                    continue;
+                }
+                if self.rule.can_merge(tile_val, next_tile_val)
+                    && !result.merges.contains(&next_tile)
+                {
+                    // A tile merges at most once per shift, so the merged
+                    // tile neither moves on nor accepts another merge.
+                    let merged = self.rule.merged(tile_val, next_tile_val);
+                    board.set(&next_tile, merged);
+                    board.set(&tile, BLANK);
+                    board.highest_merged = board.highest_merged.max(merged);
+                    let points = self.rule.points(tile_val, next_tile_val);
+                    result.gained = result.gained.saturating_add(points);
+                    result.push_step(tile, next_tile, continued);
+                    result.merges.push(next_tile);
+                    result.merged_values.push((tile_val, next_tile_val));
+                    if let Some(frames) = frames.as_mut() {
+                        frames.push(board.clone());
+                    }
+                }
+                // A blocked tile stops, the tiles ahead of it are settled.
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![cfg_attr(rustfmt, rustfmt_skip)]
+
+    use super::*;
+
+    #[test]
+    fn test_shift() {
+        let core = Core::new();
+        let mut board = Board::new(
+            4,
+            Some(
+                vec![
+                    1, 0, 0, 0,
+                    1, 0, 1, 1,
+                    1, 0, 1, 2,
+                    0, 0, 0, 0,
+                ]
+            ),
+            0
+        );
+
+        core.shift(&mut board, &Direction::Right);
+        assert_eq!(
+            board.tiles.concat(),
+            [
+                [0, 0, 0, 1],
+                [0, 0, 1, 2],
+                // The merged 2 can't merge again with the 2 next to it.
+                [0, 0, 2, 2],
+                [0, 0, 0, 0],
+            ].concat()
+        );
+
+        core.shift(&mut board, &Direction::Down);
+        assert_eq!(
+            board.tiles.concat(),
+            [
+                [0, 0, 0, 0],
+                [0, 0, 0, 0],
+                [0, 0, 1, 1],
+                [0, 0, 2, 3],
+            ].concat()
+        );
+    }
+
+    #[test]
+    fn test_game_over_policy() {
+        let board = Board::new(2, Some(vec![1, 1, 2, 3]), 0);
+        let core = Core::new();
+        assert_eq!(core.policy(), GameOverPolicy::NoMovesLeft);
+        assert!(!core.is_game_over(&board));
+        assert_eq!(core.state(&board, 11), GameState::Playing);
+
+        let core = Core::new().with_policy(GameOverPolicy::BoardFull);
+        assert!(core.is_game_over(&board));
+        assert_eq!(core.state(&board, 11), GameState::Lost);
+        assert!(!core.is_game_over(&Board::new(2, Some(vec![1, 2, 3, 0]), 0)));
+    }
+
+    #[test]
+    fn test_trace_order() {
+        let core = Core::new();
+        let board = Board::new(
+            3,
+            Some(
+                vec![
+                    1, 0, 1,
+                    0, 2, 0,
+                    2, 0, 2,
+                ]
+            ),
+            0
+        );
+        let traces = |direction| core.shift(&mut board.clone(), &direction).traces;
+        assert_eq!(traces(Direction::Right), vec![((0, 0), (0, 2)), ((1, 1), (1, 2)), ((2, 0), (2, 2))]);
+        assert_eq!(traces(Direction::Left), vec![((0, 2), (0, 0)), ((1, 1), (1, 0)), ((2, 2), (2, 0))]);
+        assert_eq!(traces(Direction::Down), vec![((0, 0), (1, 0)), ((1, 1), (2, 1)), ((0, 2), (1, 2))]);
+        assert_eq!(traces(Direction::Up), vec![((2, 0), (1, 0)), ((1, 1), (0, 1)), ((2, 2), (1, 2))]);
+
+        // Within a line the tile nearest to the edge is traced first.
+        let line = Board::new_rect(1, 4, Some(vec![1, 0, 2, 0]), 0);
+        assert_eq!(core.shift(&mut line.clone(), &Direction::Right).traces, vec![((0, 2), (0, 3)), ((0, 0), (0, 2))]);
+        let column = line.transpose();
+        assert_eq!(core.shift(&mut column.clone(), &Direction::Down).traces, vec![((2, 0), (3, 0)), ((0, 0), (2, 0))]);
+    }
+
+    #[test]
+    fn test_shift_detailed() {
+        let core = Core::new();
+        let mut board = Board::new_rect(2, 4, Some(vec![1, 1, 1, 1, 2, 2, 0, 3]), 0);
+        let (result, counts) = core.shift_detailed(&mut board, &Direction::Left);
+        assert_eq!(result.merges.len(), 3);
+        assert_eq!(counts, [(2, 2), (3, 1)].iter().copied().collect());
+        assert_eq!(board.score(), 4 + 4 + 8);
+
+        let (result, counts) = core.shift_detailed(&mut board, &Direction::Up);
+        assert!(result.is_empty());
+        assert!(counts.is_empty());
+    }
+
+    #[test]
+    fn test_game_over() {
+        let core = Core::new();
+        let board = Board::new(
+            4,
+            Some(
+                vec![
+                    1, 2, 3, 4,
+                    4, 3, 2, 1,
+                    1, 2, 3, 4,
+                    4, 3, 2, 1,
+                ]
+            ),
+            0
+        );
+        assert!(core.is_game_over(&board));
+
+        let board = Board::new(
+            4,
+            Some(
+                vec![
+                    1, 2, 3, 4,
+                    4, 3, 2, 1,
+                    1, 2, 3, 4,
+                    4, 3, 2, 4,
+                ]
+            ),
+            0
+        );
+        assert!(!core.is_game_over(&board));
+
+        let board = Board::new(
+            4,
+            Some(
+                vec![
+                    1, 2, 3, 4,
+                    4, 3, 2, 1,
+                    1, 2, 3, 5,
+                    4, 3, 2, 2,
+                ]
+            ),
+            0
+        );
+        assert!(!core.is_game_over(&board));
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let path = std::env::temp_dir().join("r2048_test_save_and_load.json");
+        let path = path.to_str().unwrap();
+        let mut board = Board::new(2, Some(vec![1, 0, 2, 3]), 12);
+        board.moves = 5;
+        board.save(path).unwrap();
+
+        let loaded = Board::load(path).unwrap();
+        assert_eq!(loaded, board);
+
+        // The square boards saved before the rows and columns still load.
+        write(path, r#"{"size":2,"tiles":[[1,0],[0,2]],"score":4}"#).unwrap();
+        assert_eq!(Board::load(path).unwrap(), Board::new(2, Some(vec![1, 0, 0, 2]), 4));
+        write(path, r#"{"size":2,"tiles":[[1,0],[2]],"score":0}"#).unwrap();
+        assert!(matches!(Board::load(path), Err(GameError::Validation(_))));
+        write(path, r#"{"rows":2,"cols":2,"tiles":[[1,0],[2]],"score":0}"#).unwrap();
+        assert!(matches!(Board::load(path), Err(GameError::Validation(_))));
+        write(path, r#"{"rows":2,"tiles":[[1,0],[0,2]],"score":0}"#).unwrap();
+        assert!(matches!(Board::load(path), Err(GameError::Serde(_))));
+        write(path, "not json").unwrap();
+        assert!(matches!(Board::load(path), Err(GameError::Serde(_))));
+        std::fs::remove_file(path).unwrap();
+        assert!(matches!(Board::load(path), Err(GameError::Io(_))));
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut board = Board::new(2, Some(vec![1, 0, 2, 63]), 0);
+        assert_eq!(board.validate(), Ok(()));
+
+        board.tiles[1][1] = 64;
+        assert_eq!(board.validate(), Err(BoardError::ImplausibleTile((1, 1), 64)));
+        board.tiles[1][1] = -1;
+        assert_eq!(board.validate(), Err(BoardError::NegativeTile(-1)));
+        board.tiles[1].pop();
+        assert_eq!(board.validate(), Err(BoardError::ColumnCount { row: 1, expected: 2, found: 1 }));
+        board.tiles.pop();
+        assert_eq!(board.validate(), Err(BoardError::RowCount { expected: 2, found: 1 }));
+    }
+
+    #[test]
+    fn test_has_won() {
+        let core = Core::new();
+        let board = Board::new(
+            4,
+            Some(
+                vec![
+                    1, 2, 3, 4,
+                    4, 3, 2, 1,
+                    1, 2, 3, 4,
+                    4, 3, 2, 11,
+                ]
+            ),
+            0
+        );
+        assert!(core.has_won(&board, 11));
+        assert!(core.has_won(&board, 10));
+        assert!(!core.has_won(&board, 12));
+    }
+
+    #[test]
+    fn test_generate_with_seed() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let core = Core::new();
+        let mut boards = Vec::new();
+        for _ in 0..2 {
+            let mut rng = StdRng::seed_from_u64(2048);
+            let mut board = Board::new(4, None, 0);
+            for direction in [Direction::Left, Direction::Up, Direction::Right].iter() {
+                board.generate_with(&mut rng, 2, 1..3);
+                core.shift(&mut board, direction);
+            }
+            boards.push(board);
+        }
+        assert_eq!(boards[0], boards[1]);
+    }
+
+    #[test]
+    fn test_shift_rect() {
+        let core = Core::new();
+        let mut board = Board::new_rect(
+            2,
+            3,
+            Some(
+                vec![
+                    1, 1, 0,
+                    0, 2, 2,
+                ]
+            ),
+            0
+        );
+
+        core.shift(&mut board, &Direction::Right);
+        assert_eq!(board.tiles, vec![vec![0, 0, 2], vec![0, 0, 3]]);
+
+        core.shift(&mut board, &Direction::Up);
+        assert_eq!(board.tiles, vec![vec![0, 0, 2], vec![0, 0, 3]]);
+
+        board.set(&(1, 0), 2);
+        core.shift(&mut board, &Direction::Up);
+        assert_eq!(board.tiles, vec![vec![2, 0, 2], vec![0, 0, 3]]);
+        assert!(!core.is_game_over(&board));
+    }
+
+    #[test]
+    fn test_render() {
+        let board = Board::new(2, Some(vec![1, 0, 11, 16]), 0);
+        assert_eq!(board.render(false), "  1  .\n 11 16\n");
+        assert_eq!(board.render(true), "     2     .\n  2048 65536\n");
+    }
+
+    #[test]
+    fn test_render_ansi() {
+        let board = Board::new_rect(1, 3, Some(vec![1, 0, 20]), 0);
+        assert_eq!(
+            board.render_ansi(),
+            "\x1b[1;38;5;250m       2\x1b[0m\x1b[2m       .\x1b[0m\x1b[1;38;5;208m 1048576\x1b[0m\n"
+        );
+    }
+
+    #[test]
+    fn test_max_tile_and_count_empty() {
+        let board = Board::new(3, None, 0);
+        assert_eq!(board.max_tile(), 0);
+        assert_eq!(board.count_empty(), 9);
+
+        let board = Board::new(
+            3,
+            Some(
+                vec![
+                    1, 0, 3,
+                    0, 7, 0,
+                    2, 0, 1,
+                ]
+            ),
+            0
+        );
+        assert_eq!(board.max_tile(), 7);
+        assert_eq!(board.count_empty(), 4);
+    }
+
+    #[test]
+    fn test_parse_direction() {
+        assert_eq!("w".parse(), Ok(Direction::Up));
+        assert_eq!("Left".parse(), Ok(Direction::Left));
+        assert_eq!("DOWN".parse(), Ok(Direction::Down));
+        assert_eq!("d".parse(), Ok(Direction::Right));
+        assert!("x".parse::<Direction>().is_err());
+        assert!("".parse::<Direction>().is_err());
+
+        assert_eq!(Direction::try_from('A'), Ok(Direction::Left));
+        assert_eq!(Direction::try_from('s'), Ok(Direction::Down));
+        assert!(Direction::try_from('q').is_err());
+    }
+
+    #[test]
+    fn test_iter() {
+        let board = Board::new(2, Some(vec![1, 0, 0, 2]), 0);
+        assert_eq!(
+            board.iter().collect::<Vec<_>>(),
+            vec![((0, 0), 1), ((0, 1), 0), ((1, 0), 0), ((1, 1), 2)]
+        );
+        assert_eq!(
+            board.iter_nonblank().collect::<Vec<_>>(),
+            vec![((0, 0), 1), ((1, 1), 2)]
+        );
+    }
+
+    #[test]
+    fn test_shift_result() {
+        let core = Core::new();
+        let mut board = Board::new(2, Some(vec![1, 1, 0, 2]), 0);
+
+        let result = core.shift(&mut board, &Direction::Right);
+        assert_eq!(result.traces, vec![((0, 0), (0, 1))]);
+        assert_eq!(result.merges, vec![(0, 1)]);
+        assert_eq!(result.gained, 4);
+        assert_eq!(board.score, 4);
+
+        let result = core.shift(&mut board, &Direction::Right);
+        assert!(result.is_empty());
+        assert_eq!(result.gained, 0);
+        assert_eq!(board.moves(), 1);
+    }
+
+    #[test]
+    fn test_tile_sum() {
+        let board = Board::new(2, Some(vec![1, 0, 11, 3]), 100);
+        assert_eq!(board.tile_sum(), 2 + 2048 + 8);
+        assert_eq!(Board::new(2, None, 0).tile_sum(), 0);
+        assert_eq!(Board::new(2, Some(vec![63, 63, 0, 0]), 0).tile_sum(), u64::MAX);
+    }
+
+    #[test]
+    fn test_apply_direction() {
+        let core = Core::new();
+        let mut board = Board::new(2, Some(vec![1, 0, 1, 0]), 0);
+        assert!(!board.apply_direction(&core, &Direction::Left));
+        assert!(board.apply_direction(&core, &Direction::Down));
+        assert_eq!(board.as_exponent_vec(), vec![0, 0, 2, 0]);
+        assert_eq!((board.score(), board.moves()), (4, 1));
+    }
+
+    #[test]
+    fn test_preview_move() {
+        let core = Core::new();
+        let board = Board::new(2, Some(vec![1, 1, 0, 0]), 0);
+        let (next, gained) = board.preview_move(&core, &Direction::Right).unwrap();
+        assert_eq!(next.as_exponent_vec(), vec![0, 2, 0, 0]);
+        assert_eq!(gained, 4);
+        assert_eq!(board.preview_move(&core, &Direction::Up), None);
+        assert_eq!(board.as_exponent_vec(), vec![1, 1, 0, 0]);
+    }
+
+    #[test]
+    fn test_neighbors() {
+        let mut board = Board::new(3, None, 0);
+        assert_eq!(board.neighbors(&(0, 0)), vec![(1, 0), (0, 1)]);
+        assert_eq!(board.neighbors(&(2, 2)), vec![(1, 2), (2, 1)]);
+        assert_eq!(board.neighbors(&(1, 1)), vec![(0, 1), (2, 1), (1, 0), (1, 2)]);
+
+        board.add_wall(&(0, 1)).unwrap();
+        assert_eq!(board.neighbors(&(1, 1)), vec![(2, 1), (1, 0), (1, 2)]);
+        assert_eq!(Board::new(1, None, 0).neighbors(&(0, 0)), vec![]);
+    }
+
+    #[test]
+    fn test_corners_and_edge_cells() {
+        let board = Board::new_rect(3, 4, None, 0);
+        assert_eq!(board.corners(), [(0, 0), (0, 3), (2, 0), (2, 3)]);
+        assert_eq!(
+            board.edge_cells(),
+            vec![(0, 0), (0, 1), (0, 2), (0, 3), (1, 0), (1, 3), (2, 0), (2, 1), (2, 2), (2, 3)]
+        );
+
+        let board = Board::new_rect(1, 2, None, 0);
+        assert_eq!(board.corners(), [(0, 0), (0, 1), (0, 0), (0, 1)]);
+        assert_eq!(board.edge_cells(), vec![(0, 0), (0, 1)]);
+    }
+
+    #[test]
+    fn test_try_from_rows() {
+        let board = Board::try_from(vec![vec![1, 0], vec![0, 2]]).unwrap();
+        assert_eq!(board, Board::new(2, Some(vec![1, 0, 0, 2]), 0));
+
+        assert_eq!(
+            Board::try_from(vec![vec![1, 0], vec![0]]),
+            Err(BoardError::ColumnCount { row: 1, expected: 2, found: 1 })
+        );
+        assert_eq!(
+            Board::try_from(vec![vec![1, 0, 0], vec![0, 2, 0]]),
+            Err(BoardError::ColumnCount { row: 0, expected: 2, found: 3 })
+        );
+        assert_eq!(Board::try_from(vec![vec![-1]]), Err(BoardError::NegativeTile(-1)));
+        assert_eq!(Board::try_from(vec![]), Err(BoardError::RowCount { expected: 1, found: 0 }));
+    }
+
+    #[test]
+    fn test_largest_cells() {
+        let board = Board::new(2, Some(vec![1, 3, 0, 3]), 0);
+        assert_eq!(board.largest_cells(2), vec![((0, 1), 3), ((1, 1), 3)]);
+        assert_eq!(board.largest_cells(5), vec![((0, 1), 3), ((1, 1), 3), ((0, 0), 1)]);
+        assert_eq!(board.largest_cells(0), vec![]);
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let board = Board::new(2, Some(vec![1, 0, 0, 2]), 0);
+        assert_eq!(board.fingerprint(), Board::new(2, Some(vec![1, 0, 0, 2]), 64).fingerprint());
+        assert_eq!(board.fingerprint(), 13464467758615495366);
+        assert_ne!(board.fingerprint(), Board::new(2, Some(vec![0, 1, 0, 2]), 0).fingerprint());
+        assert_ne!(Board::empty(2).fingerprint(), Board::empty(3).fingerprint());
+        assert_ne!(Board::new_rect(1, 2, None, 0).fingerprint(), Board::new_rect(2, 1, None, 0).fingerprint());
+
+        let mut walled = Board::new(2, Some(vec![1, 0, 0, 2]), 0);
+        walled.add_wall(&(0, 1)).unwrap();
+        assert_ne!(walled.fingerprint(), board.fingerprint());
+    }
+
+    #[test]
+    fn test_set_score() {
+        let mut board = Board::empty(2).with_score(128);
+        assert_eq!(board.score(), 128);
+        board.set_score(4);
+        assert_eq!(board.score(), 4);
+        assert_eq!(board, Board::new(2, Some(vec![0; 4]), 4));
+    }
+
+    #[test]
+    fn test_empty() {
+        let mut board = Board::empty(3);
+        assert!(board.is_empty());
+        assert_eq!((board.size(), board.score()), (Some(3), 0));
+        board.set(&(1, 1), 1);
+        assert!(!board.is_empty());
+        assert!(Board::new_rect(1, 2, Some(vec![0, 0]), 4).is_empty());
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "A blank board can't have scores.")]
+    fn test_blank_board_with_scores() {
+        Board::new(2, None, 4);
+    }
+
+    #[test]
+    fn test_is_full() {
+        let mut board = Board::new(2, Some(vec![1, 2, 3, 4]), 0);
+        assert!(board.is_full());
+        assert_eq!(board.generate(1, 1..3), vec![]);
+
+        board.set(&(1, 1), BLANK);
+        assert!(!board.is_full());
+    }
+
+    #[test]
+    fn test_rotate_and_flip() {
+        let board = Board::new_rect(2, 3, Some(vec![1, 2, 3, 4, 5, 6]), 8);
+        let cw = board.rotate_cw();
+        assert_eq!(cw.tiles, vec![vec![4, 1], vec![5, 2], vec![6, 3]]);
+        assert_eq!(cw.score(), 8);
+        assert_eq!(board.rotate_ccw().tiles, vec![vec![3, 6], vec![2, 5], vec![1, 4]]);
+        assert_eq!(board.flip_horizontal().tiles, vec![vec![3, 2, 1], vec![6, 5, 4]]);
+        assert_eq!(board.flip_vertical().tiles, vec![vec![4, 5, 6], vec![1, 2, 3]]);
+
+        assert_eq!(cw.rotate_cw().rotate_cw().rotate_cw(), board);
+        assert_eq!(cw.rotate_ccw(), board);
+        assert_eq!(board.flip_horizontal().flip_horizontal(), board);
+        assert_eq!(board.flip_vertical().flip_vertical(), board);
+
+        assert_eq!(board.transpose().tiles, vec![vec![1, 4], vec![2, 5], vec![3, 6]]);
+        assert_eq!(board.transpose().transpose(), board);
+    }
+
+    #[test]
+    fn test_init_spawn() {
+        let mut board = Board::new(4, Some(vec![1; 16]), 8);
+        let spawns = board.init_spawn(2, 1..3);
+        assert_eq!(spawns.len(), 2);
+        assert_eq!(board.count_empty(), 14);
+        assert_eq!(board.score(), 0);
+    }
+
+    #[test]
+    fn test_as_vec() {
+        let board = Board::new_rect(2, 3, Some(vec![1, 0, 2, 0, 11, 3]), 0);
+        assert_eq!((board.rows(), board.cols()), (2, 3));
+        assert_eq!(board.size(), None);
+        assert_eq!(Board::new(4, None, 0).size(), Some(4));
+        assert_eq!(board.as_exponent_vec(), vec![1, 0, 2, 0, 11, 3]);
+        assert_eq!(board.as_real_vec(), Some(vec![2, 0, 4, 0, 2048, 8]));
+    }
+
+    #[test]
+    fn test_compact() {
+        let core = Core::new();
+        let mut board = Board::new_rect(2, 4, Some(vec![0, 1, 0, 1, 2, 0, 2, 3]), 8);
+        let traces = core.compact(&mut board, &Direction::Left);
+        assert_eq!(traces, vec![((0, 1), (0, 0)), ((0, 3), (0, 1)), ((1, 2), (1, 1)), ((1, 3), (1, 2))]);
+        assert_eq!(board.as_exponent_vec(), vec![1, 1, 0, 0, 2, 2, 3, 0]);
+        assert_eq!((board.score(), board.moves()), (8, 0));
+        assert_eq!(core.compact(&mut board, &Direction::Left), vec![]);
+    }
+
+    #[test]
+    fn test_shift_tiles() {
+        let core = Core::new();
+        let mut board = Board::new(2, Some(vec![1, 1, 0, 2]), 0);
+
+        let (traces, gained) = core.shift_tiles(&mut board, &Direction::Right);
+        assert_eq!(traces, vec![((0, 0), (0, 1))]);
+        assert_eq!(gained, 4);
+        assert_eq!(board.score(), 0);
+        assert_eq!(board.moves(), 0);
+
+        board.add_score(gained);
+        assert_eq!(board.score(), 4);
+    }
+
+    #[test]
+    fn test_play_turn() {
+        let core = Core::new();
+        let mut board = Board::new(2, Some(vec![1, 1, 0, 0]), 0);
+
+        assert_eq!(core.play_turn(&mut board, &Direction::Up, 1..2), None);
+        assert_eq!(board.count_empty(), 2);
+
+        let turn = core.play_turn(&mut board, &Direction::Left, 1..2).unwrap();
+        assert_eq!(turn.traces, vec![((0, 1), (0, 0))]);
+        assert_eq!(turn.gained, 4);
+        assert_eq!(turn.state, GameState::Playing);
+        assert_eq!(turn.spawns.len(), 1);
+        assert_ne!(turn.spawns[0].0, (0, 0));
+        assert_eq!(board.get(&turn.spawns[0].0), Some(&1));
+        assert_eq!(board.count_empty(), 2);
+    }
+
+    #[test]
+    fn test_play_turn_with_spawns() {
+        let core = Core::new();
+        let mut board = Board::new(3, Some(vec![1, 0, 0, 0, 0, 0, 0, 0, 0]), 0);
+        let turn = core.play_turn_with_spawns(&mut board, &Direction::Right, 1..2, 3).unwrap();
+        assert_eq!(turn.spawns.len(), 3);
+        assert_eq!(board.count_empty(), 5);
+
+        let mut board = Board::new(2, Some(vec![1, 1, 0, 0]), 0);
+        let turn = core.play_turn_with_spawns(&mut board, &Direction::Left, 1..2, 5).unwrap();
+        assert_eq!(turn.spawns.len(), 3);
+        assert!(board.is_full());
+        let mut board = Board::new(2, Some(vec![1, 0, 0, 0]), 0);
+        let turn = core.play_turn_with_spawns(&mut board, &Direction::Right, 1..2, 0).unwrap();
+        assert!(turn.spawns.is_empty());
+        assert_eq!(board.as_exponent_vec(), vec![0, 1, 0, 0]);
+    }
+
+    #[test]
+    fn test_step() {
+        let core = Core::new();
+        let mut board = Board::new(2, Some(vec![1, 0, 1, 0]), 0);
+        assert_eq!(core.step(&mut board, &Direction::Left), None);
+
+        let turn = core.step(&mut board, &Direction::Up).unwrap();
+        assert_eq!(turn.gained, 4);
+        assert!((1..3).contains(&turn.spawns[0].1));
+        assert_eq!(board.get(&(0, 0)), Some(&2));
+        assert_eq!(board.count_empty(), 2);
+    }
+
+    #[test]
+    fn test_count_merges() {
+        let core = Core::new();
+        let board = Board::new_rect(1, 4, Some(vec![1, 1, 1, 1]), 0);
+        assert_eq!(core.count_merges(&board, &Direction::Right), 2);
+        assert_eq!(core.count_merges(&board, &Direction::Up), 0);
+        assert_eq!(board.as_exponent_vec(), vec![1, 1, 1, 1]);
+    }
+
+    #[test]
+    fn test_hint() {
+        let core = Core::new();
+        let board = Board::new(
+            3,
+            Some(
+                vec![
+                    1, 1, 0,
+                    2, 0, 0,
+                    2, 0, 0,
+                ]
+            ),
+            0
+        );
+        // Merging the 4s vertically gains more than merging the 2s.
+        assert_eq!(core.hint(&board), Some(Direction::Up));
+
+        let board = Board::new(2, Some(vec![1, 1, 0, 0]), 0);
+        // Left and Right gain the same scores and blanks, the first wins.
+        assert_eq!(core.hint(&board), Some(Direction::Left));
+
+        let board = Board::new(2, Some(vec![1, 2, 2, 1]), 0);
+        assert_eq!(core.hint(&board), None);
+    }
+
+    #[test]
+    fn test_reset() {
+        let core = Core::new();
+        let mut board = Board::new(3, Some(vec![1, 1, 0, 2, 0, 0, 0, 0, 3]), 8);
+        core.shift(&mut board, &Direction::Left);
+
+        board.reset();
+        assert_eq!(board.count_empty(), 3 * 3);
+        assert_eq!(board.score, 0);
+        assert_eq!(board.moves(), 0);
+        assert_eq!(board, Board::new(3, None, 0));
+    }
+
+    #[test]
+    fn test_share_string() {
+        let mut board = Board::new_rect(2, 3, Some(vec![1, 0, 11, 0, 0, 63]), 32);
+        board.add_wall(&(1, 0)).unwrap();
+        let shared = board.to_share_string();
+        assert_eq!(shared, "2 . 2048\n# . 9223372036854775808");
+
+        let parsed = Board::from_share_string(&shared).unwrap();
+        assert_eq!(parsed.as_exponent_vec(), board.as_exponent_vec());
+        assert!(parsed.is_wall(&(1, 0)));
+        assert_eq!(parsed.to_share_string(), shared);
+
+        assert!(Board::from_share_string("2 3\n4 8").is_err());
+        assert!(Board::from_share_string("1 2\n4 8").is_err());
+        assert!(Board::from_share_string("2 4\n8").is_err());
+        assert!(Board::from_share_string("").is_err());
+    }
+
+    #[test]
+    fn test_parse_board() {
+        let board: Board = "
+            1 . 0
+            2 3 .
+            . . 11
+        ".parse().unwrap();
+        assert_eq!(board, Board::new(3, Some(vec![1, 0, 0, 2, 3, 0, 0, 0, 11]), 0));
+
+        assert_eq!(
+            "1 2 3\n4 5 6".parse::<Board>(),
+            Err(ParseBoardError(String::from("expected a square grid but found 2 rows of 3 tiles")))
+        );
+
+        assert!("1 2\n3".parse::<Board>().is_err());
+        assert!("1 x\n3 4".parse::<Board>().is_err());
+        assert!("1 -1\n3 4".parse::<Board>().is_err());
+        assert!("".parse::<Board>().is_err());
+    }
+
+    #[test]
+    fn test_apply() {
+        let core = Core::new();
+        let mut board = Board::new(2, Some(vec![1, 0, 1, 0]), 0);
+        assert_eq!(core.apply(&mut board, &Direction::Left), Err(MoveError::NoChange(Direction::Left)));
+        assert_eq!(board.moves(), 0);
+
+        let result = core.apply(&mut board, &Direction::Up).unwrap();
+        assert_eq!(result.merges, vec![(0, 0)]);
+        assert_eq!(board.moves(), 1);
+    }
+
+    #[test]
+    fn test_replay() {
+        let core = Core::new();
+        let mut board = Board::new(2, Some(vec![1, 0, 1, 0]), 0);
+        let results = core.replay(
+            &mut board,
+            &[Direction::Left, Direction::Down, Direction::Right],
+        );
+        assert_eq!(results.len(), 3);
+        assert!(results[0].is_empty());
+        assert_eq!(results[1].merges, vec![(1, 0)]);
+        assert_eq!(results[2].traces, vec![((1, 0), (1, 1))]);
+        assert_eq!(board.tiles, vec![vec![0, 0], vec![0, 2]]);
+        assert_eq!(board.score, 4);
+        assert_eq!(board.moves(), 2);
+    }
+
+    #[test]
+    fn test_shift_steps() {
+        let core = Core::new();
+        let board = Board::new_rect(1, 4, Some(vec![0, 1, 0, 1]), 0);
+        let frames = core.shift_steps(&board, &Direction::Left);
+        let tiles: Vec<_> = frames.iter().map(|b| b.as_exponent_vec()).collect();
+        assert_eq!(tiles, vec![vec![1, 0, 0, 1], vec![1, 0, 1, 0], vec![1, 1, 0, 0], vec![2, 0, 0, 0]]);
+
+        let mut shifted = board.clone();
+        core.shift(&mut shifted, &Direction::Left);
+        assert_eq!(frames.last(), Some(&shifted));
+        assert!(core.shift_steps(&board, &Direction::Down).is_empty());
+    }
+
+    #[test]
+    fn test_replay_with_spawns() {
+        let core = Core::new();
+        let origin = Board::new(2, Some(vec![1, 0, 1, 0]), 0);
+        let moves = [Direction::Left, Direction::Down, Direction::Right, Direction::Up];
+
+        let mut board = origin.clone();
+        let results = core.replay_with_spawns(&mut board, &moves, &[((0, 0), 1), ((1, 0), 2)]).unwrap();
+        assert_eq!(results.len(), 4);
+        assert!(results[0].is_empty());
+        assert_eq!(board.tiles, vec![vec![2, 1], vec![0, 2]]);
+        assert_eq!(board.score, 4);
+
+        let mut board = origin.clone();
+        assert_eq!(
+            core.replay_with_spawns(&mut board, &moves, &[((1, 0), 1)]),
+            Err(BoardError::Occupied((1, 0)))
+        );
+        assert_eq!(board.tiles, vec![vec![0, 0], vec![2, 0]]);
+    }
+
+    #[test]
+    fn test_save_and_load_bin() {
+        let path = std::env::temp_dir().join("r2048_test_save_and_load_bin.bin");
+        let path = path.to_str().unwrap();
+        let mut board = Board::new_rect(2, 3, Some(vec![1, 0, 2, 3, 0, 17]), 300);
+        board.moves = 7;
+        board.save_bin(path).unwrap();
+        assert_eq!(std::fs::metadata(path).unwrap().len(), 16 + 6 + 1);
+
+        let loaded = Board::load_bin(path).unwrap();
+        assert_eq!(loaded, board);
+        // The files saved without the highest merged value still load.
+        assert_eq!(Board::from_bytes(&board.to_bytes().unwrap()[..22]).unwrap(), board);
+
+        write(path, &board.to_bytes().unwrap()[..20]).unwrap();
+        assert!(Board::load_bin(path).is_err());
+        write(path, "not binary").unwrap();
+        assert!(Board::load_bin(path).is_err());
+        std::fs::remove_file(path).unwrap();
+
+        board.set(&(0, 0), 256);
+        assert!(board.to_bytes().is_err());
+    }
+
+    #[test]
+    fn test_observer() {
+        #[derive(Default)]
+        struct Recorder {
+            merges: Vec<(Coordinate, Tile)>,
+            spawns: Vec<(Coordinate, Tile)>,
+            moves: Vec<Trace>,
+        }
+
+        impl GameObserver for Recorder {
+            fn on_merge(&mut self, at: Coordinate, new_value: Tile) {
+                self.merges.push((at, new_value));
+            }
+
+            fn on_spawn(&mut self, at: Coordinate, value: Tile) {
+                self.spawns.push((at, value));
+            }
+
+            fn on_move(&mut self, trace: Trace) {
+                self.moves.push(trace);
+            }
+        }
+
+        let core = Core::new();
+        let mut recorder = Recorder::default();
+        let mut board = Board::new(2, Some(vec![1, 1, 2, 0]), 0);
+
+        let result = core.shift_observed(&mut board, &Direction::Right, &mut recorder);
+        assert_eq!(recorder.moves, result.traces);
+        assert_eq!(recorder.merges, vec![((0, 1), 2)]);
+
+        board.generate_observed(1, 3..4, &mut recorder);
+        assert_eq!(recorder.spawns.len(), 1);
+        let (at, value) = recorder.spawns[0];
+        assert_eq!(value, 3);
+        assert_eq!(board.get(&at), Some(&3));
+    }
+
+    #[test]
+    fn test_generate_with_policy() {
+        let mut board = Board::new(2, Some(vec![0, 3, 0, 0]), 0);
+        let spawns = board.generate_with_policy(5, 1..2, |_, cells| *cells.last().unwrap());
+        assert_eq!(spawns, vec![((1, 1), 1), ((1, 0), 1), ((0, 0), 1)]);
+        assert!(board.is_full());
+    }
+
+    #[test]
+    fn test_generate_weighted() {
+        let mut board = Board::new(4, None, 0);
+        board.generate_weighted(8, &[(1, 0.0), (2, 1.0), (3, 0.0)]);
+        assert_eq!(board.count_empty(), 8);
+        assert!(board.iter_nonblank().all(|(_, t)| t == 2));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_generate_weighted_negative() {
+        let mut board = Board::new(4, None, 0);
+        board.generate_weighted(1, &[(1, 1.0), (2, -0.1)]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_generate_weighted_zero() {
+        let mut board = Board::new(4, None, 0);
+        board.generate_weighted(1, &[(1, 0.0)]);
+    }
+
+    #[test]
+    fn test_merge_points() {
+        assert_eq!(Core::merge_points(1), 4);
+        assert_eq!(Core::merge_points(10), 2048);
+        assert_eq!(Core::merge_points(30), 1 << 31);
+        assert_eq!(Core::merge_points(31), u32::MAX);
+        assert_eq!(Core::merge_points(100), u32::MAX);
+
+        let core = Core::new();
+        let mut board = Board::new(2, Some(vec![1, 1, 0, 0]), 0);
+        core.shift(&mut board, &Direction::Left);
+        assert_eq!(board.score, 4);
+
+        let mut board = Board::new(2, Some(vec![40, 40, 0, 0]), u32::MAX - 1);
+        let result = core.shift(&mut board, &Direction::Left);
+        assert_eq!(result.gained, u32::MAX);
+        assert_eq!(board.score(), u32::MAX);
+        assert_eq!(board.get(&(0, 0)), Some(&41));
+    }
+
+    #[test]
+    fn test_locked_cells() {
+        let core = Core::new();
+        let board = Board::new(
+            3,
+            Some(
+                vec![
+                    1, 2, 3,
+                    2, 3, 3,
+                    1, 0, 4,
+                ]
+            ),
+            0
+        );
+        assert_eq!(core.locked_cells(&board), vec![(0, 0), (0, 1), (1, 0)]);
+        assert_eq!(core.locked_cells(&Board::new(2, Some(vec![1, 2, 2, 1]), 0)).len(), 4);
+    }
+
+    #[test]
+    fn test_available_moves() {
+        let core = Core::new();
+        let board = Board::new(2, Some(vec![1, 2, 0, 0]), 0);
+        assert!(core.can_move(&board, &Direction::Down));
+        assert!(!core.can_move(&board, &Direction::Up));
+        assert_eq!(core.available_moves(&board), vec![Direction::Down]);
+
+        let board = Board::new(2, Some(vec![1, 1, 2, 3]), 0);
+        assert_eq!(core.available_moves(&board), vec![Direction::Left, Direction::Right]);
+
+        let board = Board::new(2, Some(vec![1, 2, 2, 1]), 0);
+        assert!(core.available_moves(&board).is_empty());
+
+        for tiles in [[1, 0, 0, 2], [0, 1, 1, 1], [3, 3, 0, 3]].iter() {
+            let board = Board::new(2, Some(tiles.to_vec()), 0);
+            for direction in Direction::all().iter() {
+                let mut next = board.clone();
+                let moved = !core.shift(&mut next, direction).is_empty();
+                assert_eq!(core.can_move(&board, direction), moved);
+            }
+        }
+    }
+
+    #[test]
+    fn test_try_set() {
+        let mut board = Board::new(2, None, 0);
+        assert_eq!(board.try_set(&(1, 1), 3), Ok(()));
+        assert_eq!(board.get(&(1, 1)), Some(&3));
+        assert_eq!(board.try_set(&(2, 0), 1), Err(BoardError::OutOfBounds((2, 0))));
+        assert_eq!(board.try_set(&(0, 0), -1), Err(BoardError::NegativeTile(-1)));
+        assert_eq!(board.get(&(0, 0)), Some(&0));
+    }
+
+    #[test]
+    fn test_spawn_at() {
+        let mut board = Board::new(2, Some(vec![1, 0, 0, 0]), 4);
+        assert_eq!(board.spawn_at(&(0, 1), 2), Ok(()));
+        assert_eq!(board.get(&(0, 1)), Some(&2));
+        assert_eq!(board.spawn_at(&(0, 0), 2), Err(BoardError::Occupied((0, 0))));
+        assert_eq!(board.spawn_at(&(2, 0), 2), Err(BoardError::OutOfBounds((2, 0))));
+        assert_eq!(board.spawn_at(&(1, 0), -1), Err(BoardError::NegativeTile(-1)));
+        assert_eq!(board.score(), 4);
+    }
+
+    #[test]
+    fn test_generate_at() {
+        let mut board = Board::new(2, None, 0);
+        assert_eq!(board.generate_at(&[((0, 0), 1), ((1, 1), 2)]), Ok(()));
+        assert_eq!(board.as_exponent_vec(), vec![1, 0, 0, 2]);
+
+        assert_eq!(
+            board.generate_at(&[((0, 1), 1), ((0, 1), 2)]),
+            Err(BoardError::Occupied((0, 1)))
+        );
+        assert_eq!(board.generate_at(&[((1, 0), 1), ((2, 0), 1)]), Err(BoardError::OutOfBounds((2, 0))));
+        assert_eq!(board.as_exponent_vec(), vec![1, 0, 0, 2]);
+    }
+
+    #[test]
+    fn test_walls() {
+        let core = Core::new();
+        let mut board = Board::new(4, Some(vec![0, 1, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]), 0);
+        assert_eq!(board.add_wall(&(0, 2)), Ok(()));
+        assert_eq!(board.add_wall(&(0, 2)), Err(BoardError::Occupied((0, 2))));
+        assert_eq!(board.add_wall(&(0, 1)), Err(BoardError::Occupied((0, 1))));
+        assert_eq!(board.add_wall(&(4, 0)), Err(BoardError::OutOfBounds((4, 0))));
+        assert!(board.is_wall(&(0, 2)));
+        assert_eq!(board.count_empty(), 13);
+        assert_eq!(board.spawn_at(&(0, 2), 1), Err(BoardError::Occupied((0, 2))));
+        assert_eq!(board.try_set(&(0, 2), 2), Err(BoardError::Occupied((0, 2))));
+        assert_eq!(board.count_empty(), 13);
+        assert!(!board.empty_cells().contains(&(0, 2)));
+        assert!(board.render(true).starts_with(" . 2 # 2\n"));
+
+        // The two tiles are split by the wall, so they slide without merging.
+        let result = core.shift(&mut board, &Direction::Left);
+        assert_eq!(result.gained, 0);
+        assert_eq!(&board.as_exponent_vec()[..4], &[1, 0, 0, 1]);
+        assert!(board.is_wall(&(0, 2)));
+        assert!(!core.shift(&mut board, &Direction::Right).is_empty());
+        assert_eq!(&board.as_exponent_vec()[..4], &[0, 1, 0, 1]);
+
+        let json = serde_json::to_string(&board).unwrap();
+        let loaded: Board = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded, board);
+        assert_eq!(Board::from_json_real(&board.to_json_real().unwrap()).unwrap(), board);
+
+        let mut board = Board::new(2, Some(vec![1, 0, 2, 0]), 0);
+        board.add_wall(&(0, 1)).unwrap();
+        board.add_wall(&(1, 1)).unwrap();
+        assert!(core.is_game_over(&board));
+        board.generate(4, 1..3);
+        assert_eq!(board.as_exponent_vec(), vec![1, 0, 2, 0]);
+        assert_eq!(board.rotate_cw().walls().count(), 2);
+        assert!(board.validate().is_ok());
+    }
+
+    #[test]
+    fn test_state() {
+        let core = Core::new();
+        let board = Board::new(2, Some(vec![1, 0, 2, 3]), 0);
+        assert_eq!(core.state(&board, 11), GameState::Playing);
+
+        let board = Board::new(2, Some(vec![11, 0, 2, 3]), 0);
+        assert_eq!(core.state(&board, 11), GameState::Won);
+
+        let board = Board::new(2, Some(vec![11, 1, 2, 3]), 0);
+        assert_eq!(core.state(&board, 11), GameState::Lost);
+    }
+
+    #[test]
+    fn test_tile_real() {
+        let board = Board::new(2, Some(vec![0, 1, 11, 63]), 0);
+        assert_eq!(board.tile_real(&(0, 0)), Some(0));
+        assert_eq!(board.tile_real(&(0, 1)), Some(2));
+        assert_eq!(board.tile_real(&(1, 0)), Some(2048));
+        assert_eq!(board.tile_real(&(1, 1)), Some(1 << 63));
+        assert_eq!(board.tile_real(&(2, 0)), None);
+
+        // Only `validate` rejects the tiles beyond 2^63, which have no real value.
+        let mut board = board;
+        board.set(&(1, 1), 64);
+        assert_eq!(board.tile_real(&(1, 1)), None);
+        assert_eq!(board.as_real_vec(), None);
+        assert!(matches!(board.to_json_real(), Err(GameError::Validation(_))));
+        assert!(board.render(true).ends_with(" 2048 2^64\n"));
+    }
+
+    #[test]
+    fn test_preview() {
+        let core = Core::new();
+        let board = Board::new(2, Some(vec![1, 1, 0, 2]), 0);
+        let next = core.preview(&board, &Direction::Left);
+        assert_eq!(board, Board::new(2, Some(vec![1, 1, 0, 2]), 0));
+        assert_eq!(next.tiles, vec![vec![2, 0], vec![2, 0]]);
+        assert_eq!(next.score, 4);
+        assert_eq!(next.count_empty(), 2);
+    }
+
+    #[test]
+    fn test_row_and_column() {
+        let board = Board::new_rect(2, 3, Some(vec![1, 2, 3, 4, 5, 6]), 0);
+        assert_eq!(board.row(1), Some(&[4, 5, 6][..]));
+        assert_eq!(board.row(2), None);
+        assert_eq!(board.column(2), Some(vec![3, 6]));
+        assert_eq!(board.column(3), None);
+    }
+
+    #[test]
+    fn test_rollback() {
+        let core = Core::new();
+        let origin = Board::new(
+            3,
+            Some(
+                vec![
+                    1, 0, 1,
+                    2, 2, 0,
+                    0, 3, 1,
+                ]
+            ),
+            8
+        );
+
+        let mut board = origin.clone();
+        let result = core.shift(&mut board, &Direction::Right);
+        assert_eq!(
+            result.traces,
+            vec![((0, 0), (0, 2)), ((1, 1), (1, 2)), ((1, 0), (1, 2))]
+        );
+        assert_eq!(
+            core.invert_traces(&result.traces),
+            vec![((1, 2), (1, 0)), ((1, 2), (1, 1)), ((0, 2), (0, 0))]
+        );
+        core.rollback(&mut board, &result);
+        assert_eq!(board, origin);
+
+        for direction in Direction::all().iter() {
+            let mut board = origin.clone();
+            let result = core.shift(&mut board, direction);
+            core.rollback(&mut board, &result);
+            assert_eq!(board, origin);
+        }
+
+        let mut board = origin.clone();
+        let result = core.shift(&mut board, &Direction::Right);
+        board.moves = 0;
+        core.rollback(&mut board, &result);
+        assert_eq!(board.moves, 0);
+    }
+
+    #[test]
+    fn test_generate_spawns() {
+        let mut board = Board::new(2, Some(vec![1, 0, 2, 0]), 0);
+        let spawns = board.generate(3, 5..6);
+        assert_eq!(spawns.len(), 2);
+        for (pos, value) in spawns.iter() {
+            assert_eq!(*value, 5);
+            assert_eq!(board.get(pos), Some(value));
+        }
+        assert_ne!(spawns[0].0, spawns[1].0);
+        assert_eq!(board.count_empty(), 0);
+    }
+
+    #[test]
+    fn test_generate_full_board() {
+        let mut board = Board::new(2, Some(vec![1, 2, 3, 4]), 0);
+        assert!(board.generate(2, 1..3).is_empty());
+        assert_eq!(board, Board::new(2, Some(vec![1, 2, 3, 4]), 0));
+    }
+
+    #[test]
+    fn test_direction() {
+        for direction in Direction::all().iter() {
+            assert_ne!(direction.opposite(), *direction);
+            assert_eq!(direction.opposite().opposite(), *direction);
+        }
+        assert_eq!(Direction::Up.opposite(), Direction::Down);
+        assert_eq!(Direction::Left.to_string(), "Left");
+        assert_eq!(format!("{}", Direction::Down), "Down");
+        assert_eq!(Direction::Left.opposite(), Direction::Right);
+    }
+
+    #[test]
+    fn test_merge_rule() {
+        struct ThreesRule;
+
+        impl MergeRule for ThreesRule {
+            fn can_merge(&self, a: Tile, b: Tile) -> bool {
+                a + b == 3 || (a == b && a >= 3)
+            }
+
+            fn merged(&self, a: Tile, b: Tile) -> Tile {
+                a + b
+            }
+
+            fn points(&self, a: Tile, b: Tile) -> u32 {
+                (a + b) as u32
+            }
+        }
+
+        let core = Core::with_rule(Box::new(ThreesRule));
+        let origin = Board::new_rect(
+            3,
+            2,
+            Some(
+                vec![
+                    1, 2,
+                    3, 3,
+                    1, 1,
+                ]
+            ),
+            0
+        );
+        let mut board = origin.clone();
+        let result = core.shift(&mut board, &Direction::Left);
+        assert_eq!(board.tiles, vec![vec![3, 0], vec![6, 0], vec![1, 1]]);
+        assert_eq!(board.score, 9);
+        assert_eq!(result.merged_values, vec![(2, 1), (3, 3)]);
+        assert!(!core.can_move(&board, &Direction::Left));
+        assert!(!core.is_game_over(&board));
+
+        core.rollback(&mut board, &result);
+        assert_eq!(board, origin);
+    }
+
+    #[test]
+    fn test_no_double_merge() {
+        let core = Core::new();
+        let cases = [
+            ([1, 1, 1, 1], [0, 0, 2, 2]),
+            ([2, 2, 2, 0], [0, 0, 2, 3]),
+            ([1, 0, 1, 1], [0, 0, 1, 2]),
+            ([1, 1, 2, 0], [0, 0, 2, 2]),
+            ([2, 1, 1, 0], [0, 0, 2, 2]),
+            ([1, 1, 2, 2], [0, 0, 2, 3]),
+            ([4, 1, 1, 2], [0, 4, 2, 2]),
+        ];
+        for (row, expected) in cases.iter() {
+            let mut board = Board::new_rect(1, 4, Some(row.to_vec()), 0);
+            core.shift(&mut board, &Direction::Right);
+            assert_eq!(board.tiles[0], expected.to_vec(), "shifting {:?}", row);
+        }
+    }
+
+    #[test]
+    fn test_diff() {
+        let core = Core::new();
+        let board = Board::new(2, Some(vec![1, 1, 0, 2]), 0);
+        let next = core.preview(&board, &Direction::Right);
+        assert_eq!(
+            board.diff(&next),
+            Ok(vec![((0, 0), 1, 0), ((0, 1), 1, 2)])
+        );
+        assert_eq!(board.diff(&board), Ok(vec![]));
+        assert_eq!(
+            board.diff(&Board::new(3, None, 0)),
+            Err(BoardError::SizeMismatch { expected: (2, 2), found: (3, 3) })
+        );
+    }
+
+    #[test]
+    fn test_score_gain() {
+        let core = Core::new();
+        let board = Board::new(2, Some(vec![1, 1, 2, 2]), 4);
+        assert_eq!(core.score_gain(&board, &Direction::Left), 4 + 8);
+        assert_eq!(core.score_gain(&board, &Direction::Up), 0);
+        assert_eq!(board.score(), 4);
+        assert_eq!(board.count_empty(), 0);
+    }
+
+    #[test]
+    fn test_highest_merged() {
+        let core = Core::new();
+        let mut board = Board::new_rect(1, 4, Some(vec![1, 1, 2, 0]), 0);
+        core.shift(&mut board, &Direction::Left);
+        assert_eq!(board.highest_merged(), 2);
+        let result = core.shift(&mut board, &Direction::Left);
+        assert_eq!(board.highest_merged(), 3);
+
+        let json = serde_json::to_string(&board).unwrap();
+        assert_eq!(serde_json::from_str::<Board>(&json).unwrap(), board);
+
+        core.rollback(&mut board, &result);
+        assert_eq!(board.highest_merged(), 2);
+        board.reset();
+        assert_eq!(board.highest_merged(), 0);
+    }
+
+    #[test]
+    fn test_move_history() {
+        let core = Core::new();
+        let mut board = Board::new(2, Some(vec![1, 0, 0, 0]), 0);
+        core.shift(&mut board, &Direction::Right);
+        assert_eq!(board.history(), &[]);
+
+        board.set_recording(true);
+        core.shift(&mut board, &Direction::Down);
+        core.shift(&mut board, &Direction::Down);
+        let result = core.shift(&mut board, &Direction::Left);
+        assert_eq!(board.history(), &[Direction::Down, Direction::Left]);
+
+        let json = serde_json::to_string(&board).unwrap();
+        assert_eq!(serde_json::from_str::<Board>(&json).unwrap(), board);
+        assert_eq!(Board::from_json_real(&board.to_json_real().unwrap()).unwrap(), board);
+
+        core.rollback(&mut board, &result);
+        assert_eq!(board.history(), &[Direction::Down]);
+    }
+
+    #[test]
+    fn test_json_real() {
+        let board = Board::new(2, Some(vec![1, 0, 11, 3]), 36);
+        let json = board.to_json_real().unwrap();
+        assert_eq!(json, r#"{"rows":2,"cols":2,"tiles":[[2,0],[2048,8]],"score":36,"moves":0,"highest_merged":0}"#);
+        assert_eq!(Board::from_json_real(&json).unwrap(), board);
+
+        assert!(Board::from_json_real(r#"{"rows":1,"cols":2,"tiles":[[2,6]],"score":0}"#).is_err());
+        assert!(Board::from_json_real(r#"{"rows":1,"cols":2,"tiles":[[1,2]],"score":0}"#).is_err());
+        assert!(Board::from_json_real(r#"{"rows":1,"cols":2,"tiles":[[2]],"score":0}"#).is_err());
+    }
 }
@@ -0,0 +1,319 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use rand::Rng;
+
+use super::{Board, Coordinate, Core, Direction, Tile};
+
+/// The spawned values with their probabilities used by the chance nodes.
+const SPAWNS: [(Tile, f64); 2] = [(1, 0.9), (2, 0.1)];
+
+/// The deepest search of `best_move_timed`, which stops there even if the
+/// budget is left, e.g. on a tiny board.
+const MAX_TIMED_DEPTH: u32 = 32;
+
+/// The score of a board without any move, finite so that a chance node
+/// with a losing spawn still weighs its other spawns instead of summing
+/// to negative infinity.
+const LOSS_SCORE: f64 = -1e9;
+
+/// The state shared by the nodes of a search: the evaluations of the boards
+/// seen so far, keyed by their canonical form, and the optional deadline.
+struct Search {
+    cache: HashMap<(usize, Vec<Tile>), f64>,
+    deadline: Option<Instant>,
+    timed_out: bool,
+}
+
+impl Search {
+    fn new() -> Self {
+        Search {
+            cache: HashMap::new(),
+            deadline: None,
+            timed_out: false,
+        }
+    }
+
+    /// Checks whether the deadline has passed, the scores of the nodes
+    /// are meaningless from then on.
+    fn expired(&mut self) -> bool {
+        if !self.timed_out {
+            self.timed_out = self.deadline.is_some_and(|d| Instant::now() >= d);
+        }
+        self.timed_out
+    }
+}
+
+/// Returns the smallest of the 8 rotations and reflections of the board,
+/// which all have the same evaluation.
+fn canonical(board: &Board) -> (usize, Vec<Tile>) {
+    let mut forms = Vec::with_capacity(8);
+    let mut current = board.clone();
+    for _ in 0..4 {
+        forms.push((current.rows(), current.flip_horizontal().as_exponent_vec()));
+        forms.push((current.rows(), current.as_exponent_vec()));
+        current = current.rotate_cw();
+    }
+    forms.into_iter().min().unwrap()
+}
+
+impl Board {
+    /// Spawns the tile of `SPAWNS` on the blank tile where it is the worst
+    /// for the player by `Core::evaluate`, for the hard mode. Returns the
+    /// spawned tile, or None if the board is full.
+    pub fn generate_adversarial(&mut self, core: &Core) -> Option<(Coordinate, Tile)> {
+        let mut worst: Option<((Coordinate, Tile), f64)> = None;
+        for pos in self.empty_cells() {
+            for &(value, _) in SPAWNS.iter() {
+                let mut next = self.clone();
+                next.set(&pos, value);
+                let score = core.evaluate(&next);
+                if worst.is_none_or(|(_, s)| score < s) {
+                    worst = Some(((pos, value), score));
+                }
+            }
+        }
+        let ((pos, value), _) = worst?;
+        self.set(&pos, value);
+        Some((pos, value))
+    }
+}
+
+impl Core {
+    /// Searches the best move by expectimax, looking `depth` moves ahead,
+    /// returns None if no move changes the board.
+    pub fn best_move(&self, board: &Board, depth: u32) -> Option<Direction> {
+        self.search_root(board, depth, &mut Search::new())
+    }
+
+    /// Searches the best move by expectimax with iterative deepening until
+    /// the time budget elapses, and returns the best move of the deepest
+    /// finished search. The search one move ahead always finishes, so that
+    /// a move is returned whenever one is possible.
+    pub fn best_move_timed(&self, board: &Board, time_budget: Duration) -> Option<Direction> {
+        let mut search = Search::new();
+        let mut best = self.search_root(board, 1, &mut search)?;
+        search.deadline = Some(Instant::now() + time_budget);
+        for depth in 2..=MAX_TIMED_DEPTH {
+            let found = self.search_root(board, depth, &mut search);
+            if search.expired() {
+                break;
+            }
+            best = found?;
+        }
+        Some(best)
+    }
+
+    /// Returns the board before the spawn and the gained scores of every
+    /// direction which changes the board, the board itself is untouched.
+    pub fn successors(&self, board: &Board) -> Vec<(Direction, Board, u32)> {
+        Direction::all()
+            .iter()
+            .filter_map(|direction| {
+                let (next, gained) = board.preview_move(self, direction)?;
+                Some((*direction, next, gained))
+            })
+            .collect()
+    }
+
+    /// Returns every board after a spawn of `SPAWNS` on a blank tile with its
+    /// probability, the probabilities sum to 1. Nothing is returned for a
+    /// full board.
+    pub fn spawn_outcomes(&self, board: &Board) -> Vec<(Board, f64)> {
+        let empty = board.empty_cells();
+        let mut outcomes = Vec::with_capacity(empty.len() * SPAWNS.len());
+        for pos in empty.iter() {
+            for &(value, probability) in SPAWNS.iter() {
+                let mut next = board.clone();
+                next.set(pos, value);
+                outcomes.push((next, probability / empty.len() as f64));
+            }
+        }
+        outcomes
+    }
+
+    fn search_root(&self, board: &Board, depth: u32, search: &mut Search) -> Option<Direction> {
+        let mut best: Option<(Direction, f64)> = None;
+        for (direction, next, _) in self.successors(board) {
+            let score = self.chance_node(&next, depth.saturating_sub(1), search);
+            if best.is_none_or(|(_, s)| score > s) {
+                best = Some((direction, score));
+            }
+        }
+        best.map(|(direction, _)| direction)
+    }
+
+    /// Plays uniformly random legal moves with random spawns by `SPAWNS` on a
+    /// copy of the board until no move is possible, returns the final scores
+    /// and max tile.
+    pub fn simulate_random_game<R: Rng>(&self, board: &Board, rng: &mut R) -> (u32, Tile) {
+        let mut board = board.clone();
+        loop {
+            let moves = self.available_moves(&board);
+            if moves.is_empty() {
+                break;
+            }
+            let direction = moves[rng.gen_range(0..moves.len())];
+            self.shift(&mut board, &direction);
+            board.generate_weighted_with(rng, 1, &SPAWNS);
+        }
+        (board.score(), board.max_tile())
+    }
+
+    fn max_node(&self, board: &Board, depth: u32, search: &mut Search) -> f64 {
+        let successors = self.successors(board);
+        if successors.is_empty() {
+            return LOSS_SCORE;
+        }
+        let mut best = f64::NEG_INFINITY;
+        for (_, next, _) in successors {
+            if search.expired() {
+                break;
+            }
+            best = best.max(self.chance_node(&next, depth - 1, search));
+        }
+        best
+    }
+
+    fn chance_node(&self, board: &Board, depth: u32, search: &mut Search) -> f64 {
+        if depth == 0 {
+            let key = canonical(board);
+            if let Some(&score) = search.cache.get(&key) {
+                return score;
+            }
+            let score = self.evaluate(board);
+            search.cache.insert(key, score);
+            return score;
+        }
+        let outcomes = self.spawn_outcomes(board);
+        if outcomes.is_empty() {
+            return self.max_node(board, depth, search);
+        }
+        outcomes
+            .iter()
+            .map(|(next, probability)| probability * self.max_node(next, depth, search))
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![cfg_attr(rustfmt, rustfmt_skip)]
+
+    use super::*;
+
+    #[test]
+    fn test_best_move() {
+        let core = Core::new();
+        let board = Board::new(
+            4,
+            Some(
+                vec![
+                    1, 2, 7, 7,
+                    4, 3, 2, 1,
+                    5, 4, 3, 2,
+                    6, 5, 4, 3,
+                ]
+            ),
+            0
+        );
+        // Only the 7s merge, and merging them to the right keeps the new
+        // max tile in the corner, while the left would be preferred on a tie.
+        assert_eq!(core.best_move(&board, 1), Some(Direction::Right));
+        assert_eq!(core.best_move(&board, 2), Some(Direction::Right));
+
+        // Down loses on every spawn, Right survives the spawn of a 2.
+        let board = Board::new(4, Some(vec![1, 2, 3, 4, 5, 6, 7, 8, 1, 2, 3, 4, 5, 6, 7, 0]), 0);
+        assert_eq!(core.best_move(&board, 2), Some(Direction::Right));
+        assert_eq!(core.best_move_timed(&board, Duration::from_millis(50)), Some(Direction::Right));
+
+        let board = Board::new(2, Some(vec![1, 2, 2, 1]), 0);
+        assert_eq!(core.best_move(&board, 2), None);
+    }
+
+    #[test]
+    fn test_best_move_timed() {
+        let core = Core::new();
+        let board = Board::new(4, Some(vec![3, 2, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]), 0);
+        let direction = core.best_move_timed(&board, Duration::from_millis(50)).unwrap();
+        assert!(core.can_move(&board, &direction));
+        assert!(core.best_move_timed(&board, Duration::from_secs(0)).is_some());
+
+        let board = Board::new(2, Some(vec![1, 2, 2, 1]), 0);
+        assert_eq!(core.best_move_timed(&board, Duration::from_millis(50)), None);
+    }
+
+    #[test]
+    fn test_successors() {
+        let core = Core::new();
+        let board = Board::new(2, Some(vec![1, 1, 0, 0]), 0);
+        let successors = core.successors(&board);
+        let directions: Vec<_> = successors.iter().map(|(d, _, _)| *d).collect();
+        assert_eq!(directions, vec![Direction::Down, Direction::Left, Direction::Right]);
+        assert_eq!(successors[1].1.as_exponent_vec(), vec![2, 0, 0, 0]);
+        assert_eq!(successors[1].2, 4);
+        assert_eq!(successors[0].2, 0);
+        assert_eq!(board.as_exponent_vec(), vec![1, 1, 0, 0]);
+
+        assert!(core.successors(&Board::new(2, Some(vec![1, 2, 2, 1]), 0)).is_empty());
+    }
+
+    #[test]
+    fn test_spawn_outcomes() {
+        let core = Core::new();
+        let board = Board::new(2, Some(vec![1, 0, 0, 3]), 0);
+        let outcomes = core.spawn_outcomes(&board);
+        assert_eq!(outcomes.len(), 4);
+        assert_eq!(outcomes[0], (Board::new(2, Some(vec![1, 1, 0, 3]), 0), 0.45));
+        assert_eq!(outcomes[3].0.as_exponent_vec(), vec![1, 0, 2, 3]);
+        assert!((outcomes.iter().map(|(_, p)| p).sum::<f64>() - 1.0).abs() < 1e-9);
+
+        assert!(core.spawn_outcomes(&Board::new(2, Some(vec![1, 2, 2, 1]), 0)).is_empty());
+    }
+
+    #[test]
+    fn test_generate_adversarial() {
+        let core = Core::new();
+        let board = Board::new(3, Some(vec![3, 2, 1, 0, 0, 0, 0, 0, 0]), 0);
+        let mut spawned = board.clone();
+        let (pos, value) = spawned.generate_adversarial(&core).unwrap();
+        assert_eq!(spawned.count_empty(), board.count_empty() - 1);
+        assert_eq!(spawned.get(&pos), Some(&value));
+
+        let worst = core
+            .spawn_outcomes(&board)
+            .iter()
+            .map(|(next, _)| core.evaluate(next))
+            .fold(f64::INFINITY, f64::min);
+        assert_eq!(core.evaluate(&spawned), worst);
+
+        let mut full = Board::new(2, Some(vec![1, 2, 2, 1]), 0);
+        assert_eq!(full.generate_adversarial(&core), None);
+    }
+
+    #[test]
+    fn test_canonical() {
+        let board = Board::new(2, Some(vec![3, 1, 0, 2]), 0);
+        assert_eq!(canonical(&board), canonical(&board.rotate_cw()));
+        assert_eq!(canonical(&board), canonical(&board.flip_vertical()));
+        assert_eq!(canonical(&board), (2, vec![0, 2, 3, 1]));
+    }
+
+    #[test]
+    fn test_simulate_random_game() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let core = Core::new();
+        let board = Board::new(3, Some(vec![1, 1, 0, 0, 2, 0, 0, 0, 0]), 4);
+        let (score, max) = core.simulate_random_game(&board, &mut StdRng::seed_from_u64(2048));
+        assert!(score > 4);
+        assert!(max >= 2);
+        assert_eq!(board.score(), 4);
+        assert_eq!(board.count_empty(), 6);
+
+        let lost = Board::new(2, Some(vec![1, 2, 2, 1]), 8);
+        assert_eq!(core.simulate_random_game(&lost, &mut StdRng::seed_from_u64(0)), (8, 2));
+    }
+}
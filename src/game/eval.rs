@@ -0,0 +1,323 @@
+use super::{real_value, Board, Coordinate, Core, Direction, Tile, BLANK};
+
+const EMPTY_WEIGHT: f64 = 2.7;
+const MONOTONICITY_WEIGHT: f64 = 1.0;
+const SMOOTHNESS_WEIGHT: f64 = 0.1;
+const CORNER_WEIGHT: f64 = 1.0;
+
+/// The thresholds of `is_near_loss`.
+const NEAR_LOSS_EMPTY: usize = 1;
+const NEAR_LOSS_MERGES: usize = 1;
+
+/// The sub-scores of a board contains:
+///     1.the number of blank tiles
+///     2.the penalty (<= 0) of the rows and columns not being monotonic
+///     3.the penalty (<= 0) of the differences between adjacent tiles
+///     4.whether the max tile is kept in a corner.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Heuristics {
+    pub empty: usize,
+    pub monotonicity: f64,
+    pub smoothness: f64,
+    pub max_corner: bool,
+}
+
+/// A corner of the board where a player keeps the max tile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl Corner {
+    /// Returns all corners in the order TopLeft, TopRight, BottomLeft, BottomRight.
+    pub fn all() -> [Corner; 4] {
+        [
+            Corner::TopLeft,
+            Corner::TopRight,
+            Corner::BottomLeft,
+            Corner::BottomRight,
+        ]
+    }
+
+    /// Returns the coordinate of the corner on the board.
+    pub fn position(&self, board: &Board) -> Coordinate {
+        board.corners()[*self as usize]
+    }
+}
+
+/// Returns the snake weights of a square board: the cells are ranked along
+/// a path running right on the first row, left on the second and so on,
+/// the top left cell weighs `2^(size * size - 1)` and the last cell 1.
+pub fn snake_weights(size: usize) -> Vec<Vec<f64>> {
+    let mut weights = vec![vec![0.0; size]; size];
+    for (rank, (x, y)) in snake_path(size, size).into_iter().enumerate() {
+        weights[x][y] = 2f64.powi((size * size - 1 - rank) as i32);
+    }
+    weights
+}
+
+/// Returns the coordinates in the snake order of `snake_weights`.
+fn snake_path(rows: usize, cols: usize) -> Vec<Coordinate> {
+    (0..rows)
+        .flat_map(|x| {
+            (0..cols).map(move |y| {
+                if x % 2 == 0 {
+                    (x, y)
+                } else {
+                    (x, cols - 1 - y)
+                }
+            })
+        })
+        .collect()
+}
+
+impl Core {
+    /// Sums the real values of the tiles weighted by the matrix, indexed by
+    /// the row and then the column, e.g. by `snake_weights`. The tiles out of
+    /// the matrix weigh 0.
+    pub fn positional_score(&self, board: &Board, weights: &[Vec<f64>]) -> f64 {
+        board
+            .iter_nonblank()
+            .map(|((x, y), t)| {
+                let weight = weights.get(x).and_then(|row| row.get(y)).unwrap_or(&0.0);
+                // A tile beyond 2^63 isn't a u64, but still a power of two.
+                weight * real_value(t).map_or(2f64.powi(t), |v| v as f64)
+            })
+            .sum()
+    }
+
+    /// Checks whether the non-blank tiles never increase along the snake of
+    /// `snake_weights` from the top left corner, the blanks are skipped.
+    /// The other corners are checked on the rotated or flipped board.
+    pub fn is_snake_ordered(&self, board: &Board) -> bool {
+        let tiles: Vec<Tile> = snake_path(board.rows, board.cols)
+            .iter()
+            .map(|pos| *board.get(pos).unwrap())
+            .filter(|&t| t != BLANK)
+            .collect();
+        tiles.windows(2).all(|pair| pair[0] >= pair[1])
+    }
+
+    /// Scores a board by combining its heuristics, the higher the better.
+    pub fn evaluate(&self, board: &Board) -> f64 {
+        let h = self.heuristics(board);
+        self.combine(&h, h.max_corner, board.max_tile())
+    }
+
+    /// Scores a board like `evaluate`, but the corner bonus is only given
+    /// if the max tile is kept in the chosen corner.
+    pub fn evaluate_anchored(&self, board: &Board, corner: Corner) -> f64 {
+        let h = self.heuristics(board);
+        let max = board.max_tile();
+        self.combine(&h, board.get(&corner.position(board)) == Some(&max), max)
+    }
+
+    fn combine(&self, h: &Heuristics, max_corner: bool, max: Tile) -> f64 {
+        EMPTY_WEIGHT * h.empty as f64
+            + MONOTONICITY_WEIGHT * h.monotonicity
+            + SMOOTHNESS_WEIGHT * h.smoothness
+            + if max_corner {
+                CORNER_WEIGHT * max as f64
+            } else {
+                0.0
+            }
+    }
+
+    /// Warns that the game is about to be lost, i.e. at most one blank tile
+    /// and at most one pair of adjacent mergeable tiles are left.
+    pub fn is_near_loss(&self, board: &Board) -> bool {
+        self.is_near_loss_with(board, NEAR_LOSS_EMPTY, NEAR_LOSS_MERGES)
+    }
+
+    /// Like `is_near_loss` with custom thresholds of the blank tiles and the
+    /// pairs of adjacent mergeable tiles.
+    pub fn is_near_loss_with(&self, board: &Board, max_empty: usize, max_merges: usize) -> bool {
+        board.count_empty() <= max_empty && self.count_merge_pairs(board) <= max_merges
+    }
+
+    /// Counts the pairs of adjacent non-blank tiles which could merge.
+    fn count_merge_pairs(&self, board: &Board) -> usize {
+        let mut count = 0;
+        for (pos, tile) in board.iter_nonblank() {
+            for d in [Direction::Right, Direction::Down].iter() {
+                if let Some(next) = board.next(&pos, d) {
+                    let next_tile = *board.get(&next).unwrap();
+                    if next_tile != BLANK
+                        && (self.rule.can_merge(tile, next_tile)
+                            || self.rule.can_merge(next_tile, tile))
+                    {
+                        count += 1;
+                    }
+                }
+            }
+        }
+        count
+    }
+
+    /// Computes the sub-scores used by `evaluate`.
+    pub fn heuristics(&self, board: &Board) -> Heuristics {
+        let columns: Vec<Vec<Tile>> = (0..board.cols).map(|y| board.column(y).unwrap()).collect();
+        let lines = board.tiles.iter().chain(columns.iter());
+
+        let mut monotonicity = 0.0;
+        let mut smoothness = 0.0;
+        for line in lines {
+            monotonicity += line_monotonicity(line);
+            smoothness += line_smoothness(line);
+        }
+
+        let max = board.max_tile();
+        Heuristics {
+            empty: board.count_empty(),
+            monotonicity,
+            smoothness,
+            max_corner: Corner::all()
+                .iter()
+                .any(|c| board.get(&c.position(board)) == Some(&max)),
+        }
+    }
+}
+
+/// Returns the penalty (<= 0) of the line not being sorted in either order.
+fn line_monotonicity(line: &[Tile]) -> f64 {
+    let (mut increasing, mut decreasing) = (0, 0);
+    for pair in line.windows(2) {
+        if pair[0] > pair[1] {
+            decreasing += pair[0] - pair[1];
+        } else {
+            increasing += pair[1] - pair[0];
+        }
+    }
+    -(increasing.min(decreasing) as f64)
+}
+
+/// Returns the penalty (<= 0) of the differences between adjacent non-blank tiles.
+fn line_smoothness(line: &[Tile]) -> f64 {
+    let tiles: Vec<_> = line.iter().filter(|&&t| t != BLANK).collect();
+    -(tiles.windows(2).map(|p| (p[0] - p[1]).abs()).sum::<Tile>() as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    #![cfg_attr(rustfmt, rustfmt_skip)]
+
+    use super::*;
+
+    #[test]
+    fn test_heuristics() {
+        let core = Core::new();
+        let board = Board::new(
+            3,
+            Some(
+                vec![
+                    4, 3, 2,
+                    3, 0, 1,
+                    2, 1, 0,
+                ]
+            ),
+            0
+        );
+        assert_eq!(
+            core.heuristics(&board),
+            Heuristics { empty: 2, monotonicity: -2.0, smoothness: -10.0, max_corner: true }
+        );
+
+        let scattered = Board::new(
+            3,
+            Some(
+                vec![
+                    1, 3, 1,
+                    0, 4, 0,
+                    1, 0, 1,
+                ]
+            ),
+            0
+        );
+        let h = core.heuristics(&scattered);
+        assert_eq!(h.empty, 3);
+        assert!(h.monotonicity < -2.0);
+        assert!(!h.max_corner);
+        assert!(core.evaluate(&scattered) < core.evaluate(&board));
+    }
+
+    #[test]
+    fn test_evaluate_anchored() {
+        let core = Core::new();
+        let board = Board::new(2, Some(vec![1, 0, 0, 3]), 0);
+        assert_eq!(core.evaluate_anchored(&board, Corner::BottomRight), core.evaluate(&board));
+        assert_eq!(
+            core.evaluate_anchored(&board, Corner::TopLeft),
+            core.evaluate(&board) - CORNER_WEIGHT * 3.0
+        );
+        assert_eq!(Corner::TopRight.position(&board), (0, 1));
+    }
+
+    #[test]
+    fn test_positional_score() {
+        let core = Core::new();
+        assert_eq!(snake_weights(2), vec![vec![8.0, 4.0], vec![1.0, 2.0]]);
+        assert_eq!(snake_weights(3)[1], vec![8.0, 16.0, 32.0]);
+
+        let board = Board::new(2, Some(vec![2, 0, 1, 1]), 0);
+        assert_eq!(core.positional_score(&board, &snake_weights(2)), 8.0 * 4.0 + 2.0 + 2.0 * 2.0);
+        assert_eq!(core.positional_score(&board, &[vec![1.0]]), 4.0);
+        let anchored = Board::new(2, Some(vec![0, 2, 1, 1]), 0);
+        assert!(core.positional_score(&anchored, &snake_weights(2)) < core.positional_score(&board, &snake_weights(2)));
+    }
+
+    #[test]
+    fn test_is_snake_ordered() {
+        let core = Core::new();
+        let board = Board::new(
+            3,
+            Some(
+                vec![
+                    9, 8, 7,
+                    4, 5, 6,
+                    3, 0, 1,
+                ]
+            ),
+            0
+        );
+        assert!(core.is_snake_ordered(&board));
+        assert!(!core.is_snake_ordered(&board.flip_horizontal()));
+        assert!(core.is_snake_ordered(&Board::new_rect(2, 3, Some(vec![5, 4, 3, 0, 1, 2]), 0)));
+        assert!(!core.is_snake_ordered(&Board::new(2, Some(vec![3, 2, 2, 1]), 0)));
+    }
+
+    #[test]
+    fn test_is_near_loss() {
+        let core = Core::new();
+        let board = Board::new(
+            3,
+            Some(
+                vec![
+                    1, 2, 3,
+                    2, 3, 1,
+                    3, 1, 0,
+                ]
+            ),
+            0
+        );
+        assert!(core.is_near_loss(&board));
+
+        let board = Board::new(
+            3,
+            Some(
+                vec![
+                    1, 1, 3,
+                    2, 3, 3,
+                    3, 1, 0,
+                ]
+            ),
+            0
+        );
+        assert!(!core.is_near_loss(&board));
+        assert!(core.is_near_loss_with(&board, 1, 3));
+
+        assert!(!core.is_near_loss(&Board::new(3, None, 0)));
+    }
+}
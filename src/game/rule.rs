@@ -0,0 +1,32 @@
+use super::{Core, Tile};
+
+/// Decides how two tiles merge, which allows variants like Threes
+/// without changing the shift logic. `a` is the stored value of the moving
+/// tile and `b` the one of the tile it moves into.
+pub trait MergeRule {
+    fn can_merge(&self, a: Tile, b: Tile) -> bool;
+
+    /// Returns the stored value of the merged tile.
+    fn merged(&self, a: Tile, b: Tile) -> Tile;
+
+    /// Returns the scores gained by the merge.
+    fn points(&self, a: Tile, b: Tile) -> u32;
+}
+
+/// The rule of the classic 2048, two equal tiles merge into the next power of two.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ClassicRule;
+
+impl MergeRule for ClassicRule {
+    fn can_merge(&self, a: Tile, b: Tile) -> bool {
+        a == b
+    }
+
+    fn merged(&self, a: Tile, _b: Tile) -> Tile {
+        a.saturating_add(1)
+    }
+
+    fn points(&self, a: Tile, _b: Tile) -> u32 {
+        Core::merge_points(a)
+    }
+}
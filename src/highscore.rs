@@ -0,0 +1,63 @@
+use std::fs::{read_to_string, write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::GameError;
+
+/// The best score across sessions, which is kept apart from the board so
+/// that library users can skip touching the filesystem.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HighScore {
+    best: u32,
+}
+
+impl HighScore {
+    pub fn best(&self) -> u32 {
+        self.best
+    }
+
+    /// Records the score, returns true if it sets a new record.
+    pub fn update(&mut self, score: u32) -> bool {
+        if score > self.best {
+            self.best = score;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Saves the high score formatted as json to the given path.
+    pub fn save(&self, path: &str) -> Result<(), GameError> {
+        write(path, serde_json::to_string(&self)?)?;
+        log::debug!("Saved to file: {}", path);
+        Ok(())
+    }
+
+    /// Loads the high score formatted as json from the given path.
+    pub fn load(path: &str) -> Result<HighScore, GameError> {
+        let high_score = serde_json::from_str(&read_to_string(path)?)?;
+        log::debug!("Loaded from file: {}", path);
+        Ok(high_score)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_high_score() {
+        let path = std::env::temp_dir().join("r2048_test_high_score.json");
+        let path = path.to_str().unwrap();
+        let mut high_score = HighScore::default();
+        assert!(high_score.update(16));
+        assert!(!high_score.update(8));
+        assert!(!high_score.update(16));
+        assert_eq!(high_score.best(), 16);
+
+        high_score.save(path).unwrap();
+        assert_eq!(HighScore::load(path).unwrap(), high_score);
+        std::fs::remove_file(path).unwrap();
+        assert!(HighScore::load(path).is_err());
+    }
+}
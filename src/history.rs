@@ -0,0 +1,168 @@
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::GameConfig;
+use crate::game::{Board, BoardError, Core, Direction, MoveResult, TurnResult};
+
+pub(crate) const DEFAULT_DEPTH: usize = 16;
+
+/// A bounded history of board snapshots, which allows to undo and redo moves.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct History {
+    depth: usize,
+    undo: VecDeque<Board>,
+    redo: Vec<Board>,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new(DEFAULT_DEPTH)
+    }
+}
+
+impl History {
+    /// Creates a history keeping at most `depth` snapshots.
+    pub fn new(depth: usize) -> Self {
+        History {
+            depth,
+            undo: VecDeque::with_capacity(depth),
+            redo: Vec::new(),
+        }
+    }
+
+    /// Shifts the board like `Core::shift` and remembers the previous
+    /// situation if any tile moved.
+    pub fn shift(&mut self, core: &Core, board: &mut Board, direction: &Direction) -> MoveResult {
+        let snapshot = board.clone();
+        let result = core.shift(board, direction);
+        if !result.is_empty() {
+            self.record(snapshot);
+        }
+        result
+    }
+
+    /// Plays a turn like `Core::play_turn_with_config` and remembers the
+    /// previous situation if the move is valid, so the spawns are undone
+    /// with it.
+    pub fn play_turn(
+        &mut self,
+        core: &Core,
+        board: &mut Board,
+        direction: &Direction,
+        config: &GameConfig,
+    ) -> Option<TurnResult> {
+        let snapshot = board.clone();
+        let turn = core.play_turn_with_config(board, direction, config)?;
+        self.record(snapshot);
+        Some(turn)
+    }
+
+    /// Restores the board to the situation before the last shift,
+    /// returns false if there is nothing to undo.
+    pub fn undo(&mut self, board: &mut Board) -> bool {
+        match self.undo.pop_back() {
+            Some(snapshot) => {
+                self.redo.push(std::mem::replace(board, snapshot));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Validates every snapshot to undo or redo like `Board::validate`, e.g.
+    /// after loading a saved history.
+    pub(crate) fn validate(&self) -> Result<(), BoardError> {
+        self.undo
+            .iter()
+            .chain(self.redo.iter())
+            .try_for_each(Board::validate)
+    }
+
+    /// Reapplies the last undone situation, returns false if there is nothing to redo.
+    pub fn redo(&mut self, board: &mut Board) -> bool {
+        match self.redo.pop() {
+            Some(snapshot) => {
+                let current = std::mem::replace(board, snapshot);
+                self.push(current);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn record(&mut self, snapshot: Board) {
+        self.redo.clear();
+        self.push(snapshot);
+    }
+
+    fn push(&mut self, snapshot: Board) {
+        if self.depth == 0 {
+            return;
+        }
+        if self.undo.len() == self.depth {
+            self.undo.pop_front();
+        }
+        self.undo.push_back(snapshot);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![cfg_attr(rustfmt, rustfmt_skip)]
+
+    use super::*;
+
+    #[test]
+    fn test_undo_and_redo() {
+        let core = Core::new();
+        let mut history = History::default();
+        let mut board = Board::new(2, Some(vec![1, 1, 0, 0]), 0);
+        let origin = board.clone();
+
+        assert!(!history.undo(&mut board));
+        history.shift(&core, &mut board, &Direction::Right);
+        let shifted = board.clone();
+        assert_ne!(origin, shifted);
+
+        assert!(history.undo(&mut board));
+        assert_eq!(board, origin);
+        assert!(!history.undo(&mut board));
+
+        assert!(history.redo(&mut board));
+        assert_eq!(board, shifted);
+        assert!(!history.redo(&mut board));
+    }
+
+    #[test]
+    fn test_play_turn() {
+        let core = Core::new();
+        let mut history = History::default();
+        let mut board = Board::new(2, Some(vec![1, 0, 0, 0]), 0);
+        let origin = board.clone();
+
+        let config = GameConfig { rows: 2, cols: 2, ..GameConfig::default() };
+        assert_eq!(history.play_turn(&core, &mut board, &Direction::Left, &config), None);
+        assert!(!history.undo(&mut board));
+        assert!(history.play_turn(&core, &mut board, &Direction::Right, &config).is_some());
+        assert_eq!(board.count_empty(), 2);
+        assert!(history.undo(&mut board));
+        assert_eq!(board, origin);
+    }
+
+    #[test]
+    fn test_history_depth() {
+        let core = Core::new();
+        let mut history = History::new(1);
+        let mut board = Board::new(2, Some(vec![1, 0, 0, 0]), 0);
+
+        history.shift(&core, &mut board, &Direction::Right);
+        history.shift(&core, &mut board, &Direction::Down);
+        // The no-op shift must not be recorded.
+        history.shift(&core, &mut board, &Direction::Down);
+
+        assert!(history.undo(&mut board));
+        assert_eq!(board.get(&(0, 1)), Some(&1));
+        assert!(!history.undo(&mut board));
+    }
+}
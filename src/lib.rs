@@ -1,50 +1,90 @@
-mod game;
+// Without the default `std` feature only the packed `BitBoard` is built,
+// e.g. for a microcontroller driving a 4x4 LED matrix.
+#![cfg_attr(not(feature = "std"), no_std)]
 
-pub use game::{Board, Core, Direction};
+mod bitboard;
+#[cfg(feature = "std")]
+mod config;
+#[cfg(feature = "facade")]
+mod facade;
+#[cfg(feature = "std")]
+mod game;
+#[cfg(feature = "std")]
+mod highscore;
+#[cfg(feature = "std")]
+mod history;
+#[cfg(feature = "std")]
+mod replay;
+#[cfg(feature = "std")]
+mod session;
+mod types;
 
-use std::io::stdin;
+pub use bitboard::BitBoard;
+#[cfg(feature = "std")]
+pub use config::{GameConfig, SIZES};
+#[cfg(feature = "facade")]
+pub use facade::Game;
+#[cfg(feature = "std")]
+pub use game::{
+    snake_weights, Board, BoardError, ClassicRule, Core, Corner, GameError, GameObserver,
+    GameOverPolicy, GameState, Heuristics, MergeRule, MoveError, MoveResult, ParseBoardError,
+    TurnResult, TARGET,
+};
+#[cfg(feature = "std")]
+pub use highscore::HighScore;
+#[cfg(feature = "std")]
+pub use history::History;
+#[cfg(feature = "std")]
+pub use replay::{Replay, REPLAY_VERSION};
+#[cfg(feature = "std")]
+pub use session::Session;
+#[cfg(feature = "std")]
+pub use types::ParseDirectionError;
+pub use types::{Coordinate, Direction, Tile, Trace};
 
+#[cfg(feature = "std")]
 use log::{info, warn};
-use log4rs::init_file;
 
+/// Plays the game automatically by the expectimax search looking `depth`
+/// moves ahead, until no move is possible, and returns the final board.
+#[cfg(feature = "std")]
+pub fn run_ai(depth: u32) -> Board {
+    info!("Welcome to Rust 2048 AI ~");
 
-pub fn run() {
-    init_file("config/log4rs.yaml", Default::default()).unwrap();
-    info!("Welcome to Rust 2048 ~");
+    let config = GameConfig::default();
+    let core = Core::from_config(&config);
+    let mut board = Board::new_game(&config);
 
-    let core = Core::new();
-    let mut board = Board::new(4, None, 0);
-
-    while !core.is_game_over(&board) {
-        board.generate(1, 1..3);
+    loop {
         info!("{}", board);
 
-        loop {
-            info!("Input direction(w,a,s,d): ");
-            let mut direction_str = String::new();
-            stdin().read_line(&mut direction_str).unwrap();
-
-            let traces = core.shift(
-                &mut board,
-                match direction_str.trim().to_lowercase().as_str() {
-                    "w" => &Direction::Up,
-                    "a" => &Direction::Left,
-                    "s" => &Direction::Down,
-                    "d" => &Direction::Right,
-                    _ => {
-                        warn!("Invalid input!");
-                        continue;
-                    }
-                },
-            );
-            info!("Traces: {:?}", traces);
-            if traces.is_empty() {
-                warn!("Invalid moved!");
-                continue;
+        match core.best_move(&board, depth) {
+            Some(direction) => {
+                info!("Direction: {}", direction);
+                core.shift(&mut board, &direction);
+                board.generate_weighted(config.spawns_for_size(), &config.spawn_weights);
             }
-            break;
+            None => break,
         }
     }
 
-    info!("{}", board);
-}
\ No newline at end of file
+    let max_tile = board
+        .largest_cells(1)
+        .first()
+        .and_then(|(pos, _)| board.tile_real(pos));
+    match max_tile {
+        Some(max_tile) => info!(
+            "Game over! Score: {}, max tile: {}, moves: {}",
+            board.score(),
+            max_tile,
+            board.moves()
+        ),
+        None => warn!(
+            "Game over! Score: {}, max tile beyond 2^63: 2^{}, moves: {}",
+            board.score(),
+            board.max_tile(),
+            board.moves()
+        ),
+    }
+    board
+}
@@ -1,8 +1,136 @@
-use r2048::run;
+use std::{env::args, io::stdin};
 
-extern crate log;
-extern crate log4rs;
+use log::{debug, info, warn};
+use log4rs::init_file;
+use r2048::{run_ai, Board, Core, Direction, GameConfig, GameState, HighScore, History, SIZES};
+
+/// The search depth of the AI if not given.
+const DEFAULT_DEPTH: u32 = 2;
+
+const HIGH_SCORE_PATH: &str = "highscore.json";
 
 fn main() {
-    run();
+    init_file("config/log4rs.yaml", Default::default()).unwrap();
+
+    let mut args: Vec<String> = args().skip(1).collect();
+    // The logs go to stderr, so that the json is the only output on stdout.
+    let json = args.iter().any(|a| a == "--json");
+    args.retain(|a| a != "--json");
+
+    let board = match args.first().map(String::as_str) {
+        Some("--ai") => {
+            let depth = args.get(1).and_then(|d| d.parse().ok());
+            run_ai(depth.unwrap_or(DEFAULT_DEPTH))
+        }
+        Some("--size") => run(config_of_size(args.get(1))),
+        _ => run(GameConfig::default()),
+    };
+
+    if json {
+        match serde_json::to_string(&board) {
+            Ok(json) => println!("{}", json),
+            Err(e) => warn!("Failed to dump the board: {}", e),
+        }
+    }
+}
+
+/// Returns the config of the size given on the command line, falling back to
+/// the default 4x4 if it is missing or invalid.
+fn config_of_size(size: Option<&String>) -> GameConfig {
+    match size
+        .and_then(|s| s.parse().ok())
+        .and_then(GameConfig::with_size)
+    {
+        Some(config) => config,
+        None => {
+            warn!(
+                "The size must be in {}..={}, the default 4x4 is used.",
+                SIZES.start(),
+                SIZES.end()
+            );
+            GameConfig::default()
+        }
+    }
+}
+
+/// Plays the game on the console and returns the final board, the library
+/// only steps the turns so the blocking input stays here.
+fn run(config: GameConfig) -> Board {
+    info!("Welcome to Rust 2048 ~");
+
+    let core = Core::from_config(&config);
+    let mut history = History::from_config(&config);
+    let mut board = Board::new_game(&config);
+    let mut endless = false;
+
+    loop {
+        info!("{}", board);
+
+        match core.state(&board, config.target) {
+            GameState::Lost => break,
+            GameState::Won if !endless => {
+                info!("You win! Keep playing? (y/n): ");
+                let mut answer = String::new();
+                stdin().read_line(&mut answer).unwrap();
+                if !answer.trim().eq_ignore_ascii_case("y") {
+                    break;
+                }
+                endless = true;
+            }
+            _ => {}
+        }
+
+        loop {
+            info!("Input direction(w,a,s,d) or undo(u), redo(r): ");
+            let mut direction_str = String::new();
+            stdin().read_line(&mut direction_str).unwrap();
+
+            let direction: Direction = match direction_str.trim().to_lowercase().as_str() {
+                "u" => {
+                    if history.undo(&mut board) {
+                        info!("{}", board);
+                    } else {
+                        warn!("Nothing to undo!");
+                    }
+                    continue;
+                }
+                "r" => {
+                    if history.redo(&mut board) {
+                        info!("{}", board);
+                    } else {
+                        warn!("Nothing to redo!");
+                    }
+                    continue;
+                }
+                input => match input.parse() {
+                    Ok(direction) => direction,
+                    Err(e) => {
+                        warn!("Invalid input! {}", e);
+                        continue;
+                    }
+                },
+            };
+
+            match history.play_turn(&core, &mut board, &direction, &config) {
+                Some(turn) => {
+                    info!("{}: {:?}", direction, turn.traces);
+                    debug!("Spawns: {:?}", turn.spawns);
+                    break;
+                }
+                None => warn!("Invalid moved!"),
+            }
+        }
+    }
+
+    info!("Game over! Score: {}", board.score());
+    let mut high_score = HighScore::load(HIGH_SCORE_PATH).unwrap_or_default();
+    if high_score.update(board.score()) {
+        info!("New high score!");
+        if let Err(e) = high_score.save(HIGH_SCORE_PATH) {
+            warn!("Failed to save the high score: {}", e);
+        }
+    } else {
+        info!("High score: {}", high_score.best());
+    }
+    board
 }
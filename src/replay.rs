@@ -0,0 +1,118 @@
+use std::fs::{read_to_string, write};
+
+use rand::{rngs::StdRng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::config::GameConfig;
+use crate::game::{Board, Core, GameError};
+use crate::types::Direction;
+
+/// The version of the replay format written by this crate.
+pub const REPLAY_VERSION: u32 = 1;
+
+/// A shareable record of a game: the seed of the spawns, the config and the
+/// moves, which `Core::play_replay` plays again. The spawns depend on the
+/// `StdRng` of the `rand` version, unlike `Core::replay_with_spawns`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u32,
+    pub seed: u64,
+    pub config: GameConfig,
+    pub moves: Vec<Direction>,
+}
+
+impl Replay {
+    /// Starts an empty replay of the current version.
+    pub fn new(seed: u64, config: GameConfig) -> Self {
+        Replay {
+            version: REPLAY_VERSION,
+            seed,
+            config,
+            moves: Vec::new(),
+        }
+    }
+
+    /// Saves the replay formatted as json to the given path.
+    pub fn save(&self, path: &str) -> Result<(), GameError> {
+        write(path, serde_json::to_string(&self)?)?;
+        log::debug!("Saved to file: {}", path);
+        Ok(())
+    }
+
+    /// Loads the replay formatted as json from the given path, a replay of
+    /// another version is rejected.
+    pub fn load(path: &str) -> Result<Replay, GameError> {
+        let replay: Replay = serde_json::from_str(&read_to_string(path)?)?;
+        if replay.version != REPLAY_VERSION {
+            return Err(GameError::Validation(format!(
+                "Unsupported replay version {}, expected {}.",
+                replay.version, REPLAY_VERSION
+            )));
+        }
+        log::debug!("Loaded from file: {}", path);
+        Ok(replay)
+    }
+}
+
+impl Core {
+    /// Plays the replay from a blank board: spawns the start tiles and then
+    /// the tiles of every valid move by the seeded generator, the invalid
+    /// moves are skipped without spawning. Returns the final board.
+    pub fn play_replay(&self, replay: &Replay) -> Board {
+        let config = &replay.config;
+        let mut rng = StdRng::seed_from_u64(replay.seed);
+        let mut board = Board::from_config(config);
+        board.generate_weighted_with(&mut rng, config.start_tiles, &config.spawn_weights);
+        for direction in replay.moves.iter() {
+            if board.apply_direction(self, direction) {
+                let count = config.spawns_for_size();
+                board.generate_weighted_with(&mut rng, count, &config.spawn_weights);
+            }
+        }
+        board
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_play_replay() {
+        let core = Core::new();
+        let mut replay = Replay::new(2048, GameConfig::default());
+        replay.moves = vec![
+            Direction::Left,
+            Direction::Up,
+            Direction::Right,
+            Direction::Down,
+        ];
+
+        let mut rng = StdRng::seed_from_u64(2048);
+        let mut expected = Board::from_config(&replay.config);
+        let weights = [(1, 0.9), (2, 0.1)];
+        expected.generate_weighted_with(&mut rng, 2, &weights);
+        for direction in replay.moves.iter() {
+            if expected.apply_direction(&core, direction) {
+                expected.generate_weighted_with(&mut rng, 1, &weights);
+            }
+        }
+        assert_eq!(core.play_replay(&replay), expected);
+        assert!(expected.moves() > 0);
+    }
+
+    #[test]
+    fn test_save_and_load_replay() {
+        let path = std::env::temp_dir().join("r2048_test_replay.json");
+        let path = path.to_str().unwrap();
+        let mut replay = Replay::new(7, GameConfig::default());
+        replay.moves.push(Direction::Up);
+        replay.save(path).unwrap();
+        assert_eq!(Replay::load(path).unwrap(), replay);
+
+        replay.version = REPLAY_VERSION + 1;
+        replay.save(path).unwrap();
+        assert!(matches!(Replay::load(path), Err(GameError::Validation(_))));
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,155 @@
+use std::fs::{read_to_string, write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::GameConfig;
+use crate::game::{Board, Core, GameError, MoveResult};
+use crate::highscore::HighScore;
+use crate::history::History;
+use crate::types::Direction;
+
+/// The whole state of a game for resuming it exactly, including the undo
+/// history and the log of the successful moves.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    pub config: GameConfig,
+    pub board: Board,
+    pub history: History,
+    pub high_score: HighScore,
+    pub moves: Vec<Direction>,
+    undone: Vec<Direction>,
+}
+
+impl Session {
+    /// Starts a new game with the configured start tiles.
+    pub fn new(config: GameConfig) -> Self {
+        let board = Board::new_game(&config);
+        Session {
+            history: History::from_config(&config),
+            config,
+            board,
+            high_score: HighScore::default(),
+            moves: Vec::new(),
+            undone: Vec::new(),
+        }
+    }
+
+    /// Shifts the board through the history and, if any tile moved, logs
+    /// the move, records the score in the high score and spawns
+    /// `GameConfig::spawns_for_size` tiles by the configured weights.
+    pub fn shift(&mut self, core: &Core, direction: &Direction) -> MoveResult {
+        let result = self.history.shift(core, &mut self.board, direction);
+        if !result.is_empty() {
+            self.moves.push(*direction);
+            self.undone.clear();
+            self.high_score.update(self.board.score());
+            let count = self.config.spawns_for_size();
+            self.board
+                .generate_weighted(count, &self.config.spawn_weights);
+        }
+        result
+    }
+
+    /// Undoes the last move like `History::undo` and drops it from the log.
+    pub fn undo(&mut self) -> bool {
+        if !self.history.undo(&mut self.board) {
+            return false;
+        }
+        if let Some(direction) = self.moves.pop() {
+            self.undone.push(direction);
+        }
+        true
+    }
+
+    /// Redoes the last undone move like `History::redo` and logs it again.
+    pub fn redo(&mut self) -> bool {
+        if !self.history.redo(&mut self.board) {
+            return false;
+        }
+        if let Some(direction) = self.undone.pop() {
+            self.moves.push(direction);
+        }
+        true
+    }
+
+    /// Saves the session formatted as json to the given path.
+    pub fn save(&self, path: &str) -> Result<(), GameError> {
+        write(path, serde_json::to_string(&self)?)?;
+        log::debug!("Saved to file: {}", path);
+        Ok(())
+    }
+
+    /// Loads the session formatted as json from the given path, validating
+    /// the board and every snapshot of the history.
+    pub fn load(path: &str) -> Result<Session, GameError> {
+        let session: Session = serde_json::from_str(&read_to_string(path)?)?;
+        session.board.validate()?;
+        session.history.validate()?;
+        log::debug!("Loaded from file: {}", path);
+        Ok(session)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_and_load_session() {
+        let path = std::env::temp_dir().join("r2048_test_session.json");
+        let path = path.to_str().unwrap();
+        let core = Core::new();
+        let mut session = Session::new(GameConfig::default());
+        for direction in Direction::all().iter() {
+            session.shift(&core, direction);
+        }
+        let played = session.moves.clone();
+        assert!(!played.is_empty());
+        session.save(path).unwrap();
+
+        let mut loaded = Session::load(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(loaded.board, session.board);
+        assert_eq!(loaded.moves, played);
+
+        assert!(loaded.undo());
+        assert_eq!(loaded.moves, played[..played.len() - 1].to_vec());
+        assert!(loaded.redo());
+        assert_eq!(loaded.moves, played);
+    }
+
+    #[test]
+    fn test_high_score() {
+        let core = Core::new();
+        let mut session = Session::new(GameConfig::default());
+        session.board = Board::new(
+            4,
+            Some(vec![1, 1, 0, 0, 2, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]),
+            0,
+        );
+        session.shift(&core, &Direction::Left);
+        assert_eq!(session.high_score.best(), 12);
+
+        assert!(session.undo());
+        assert_eq!(session.board.score(), 0);
+        assert_eq!(session.high_score.best(), 12);
+    }
+
+    #[test]
+    fn test_load_invalid_history() {
+        let path = std::env::temp_dir().join("r2048_test_invalid_history.json");
+        let path = path.to_str().unwrap();
+        let core = Core::new();
+        let mut session = Session::new(GameConfig::default());
+        for direction in Direction::all().iter() {
+            session.shift(&core, direction);
+        }
+
+        let mut json = serde_json::to_value(&session).unwrap();
+        json["history"]["undo"][0]["tiles"][0][0] = serde_json::json!(-1);
+        std::fs::write(path, json.to_string()).unwrap();
+        let loaded = Session::load(path);
+        std::fs::remove_file(path).unwrap();
+        assert!(matches!(loaded, Err(GameError::Validation(_))));
+    }
+}
@@ -0,0 +1,102 @@
+use core::fmt;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "std")]
+use std::{convert::TryFrom, error, str::FromStr};
+
+pub type Tile = i32;
+pub type Coordinate = (usize, usize);
+pub type Trace = (Coordinate, Coordinate);
+
+/// The stored value of a blank tile.
+pub(crate) const BLANK: Tile = 0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    /// Returns all directions in the order Up, Down, Left, Right.
+    pub fn all() -> [Direction; 4] {
+        [
+            Direction::Up,
+            Direction::Down,
+            Direction::Left,
+            Direction::Right,
+        ]
+    }
+
+    /// Returns the reverse direction.
+    pub fn opposite(&self) -> Direction {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+        }
+    }
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Direction::Up => "Up",
+            Direction::Down => "Down",
+            Direction::Left => "Left",
+            Direction::Right => "Right",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The error returned when a direction can't be parsed from user input.
+#[cfg(feature = "std")]
+#[derive(Debug, PartialEq)]
+pub struct ParseDirectionError(String);
+
+#[cfg(feature = "std")]
+impl fmt::Display for ParseDirectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Unknown direction: {:?}", self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for ParseDirectionError {}
+
+#[cfg(feature = "std")]
+impl FromStr for Direction {
+    type Err = ParseDirectionError;
+
+    /// Parses "w", "a", "s", "d" or "up", "left", "down", "right", ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "w" | "up" => Ok(Direction::Up),
+            "a" | "left" => Ok(Direction::Left),
+            "s" | "down" => Ok(Direction::Down),
+            "d" | "right" => Ok(Direction::Right),
+            _ => Err(ParseDirectionError(s.to_string())),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl TryFrom<char> for Direction {
+    type Error = ParseDirectionError;
+
+    /// Converts the single-letter forms "w", "a", "s", "d", ignoring case.
+    fn try_from(c: char) -> Result<Self, Self::Error> {
+        match c.to_ascii_lowercase() {
+            'w' => Ok(Direction::Up),
+            'a' => Ok(Direction::Left),
+            's' => Ok(Direction::Down),
+            'd' => Ok(Direction::Right),
+            _ => Err(ParseDirectionError(c.to_string())),
+        }
+    }
+}